@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::run::stream_seed;
+use crate::{Aggregate, Params, RunConfig};
+
+/// One entry of a `--params-file` array: a name plus the four parameters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NamedParams {
+    pub name: String,
+    #[serde(flatten)]
+    pub params: Params,
+}
+
+/// One element of the batch output array.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BatchRow {
+    pub name: String,
+    pub params: Params,
+    pub aggregate: Aggregate,
+}
+
+pub fn load(path: &Path) -> Result<Vec<NamedParams>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("cannot read params file {}: {}", path.display(), err))?;
+    parse(&text).map_err(|err| format!("invalid params file {}: {}", path.display(), err))
+}
+
+pub fn parse(text: &str) -> Result<Vec<NamedParams>, String> {
+    serde_json::from_str(text).map_err(|err| err.to_string())
+}
+
+/// Runs `base` once per set. Set `i` uses `stream_seed(base.seed, i)`, so the
+/// first set matches a plain run and every set has its own stream.
+pub fn run_sets(base: &RunConfig, sets: &[NamedParams]) -> Vec<BatchRow> {
+    sets.iter()
+        .enumerate()
+        .map(|(i, set)| {
+            let params = set.params.clamped_to(base.bounds);
+            let config = RunConfig {
+                params,
+                seed: stream_seed(base.seed, i as u64),
+                ..*base
+            };
+            BatchRow {
+                name: set.name.clone(),
+                params,
+                aggregate: config.run().1,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,46 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::{Lcg, Pcg64, Rng, RngKind, RunConfig};
+
+/// Untimed episodes run before the measured ones, so caches and branch
+/// predictors settle first.
+pub const BENCH_WARMUP: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BenchReport {
+    pub episodes: usize,
+    pub wall: Duration,
+}
+
+impl BenchReport {
+    pub fn episodes_per_s(&self) -> f64 {
+        self.episodes as f64 / self.wall.as_secs_f64()
+    }
+}
+
+/// Times `config.episodes` calls of `run_episode` on one thread, discarding
+/// the episodes. Each generator gets its own monomorphized loop so `--rng`
+/// choices compare without enum dispatch in the way.
+pub fn bench(config: &RunConfig) -> BenchReport {
+    let wall = match config.rng {
+        RngKind::Lcg => time_episodes(config, Lcg::new(config.seed)),
+        RngKind::Pcg => time_episodes(config, Pcg64::new(config.seed)),
+    };
+    BenchReport {
+        episodes: config.episodes,
+        wall,
+    }
+}
+
+fn time_episodes<R: Rng>(config: &RunConfig, mut rng: R) -> Duration {
+    let (model, params) = (&config.model, config.params);
+    for _ in 0..BENCH_WARMUP.min(config.episodes) {
+        black_box(model.run_episode(params, &mut rng));
+    }
+    let start = Instant::now();
+    for _ in 0..config.episodes {
+        black_box(model.run_episode(params, &mut rng));
+    }
+    start.elapsed()
+}
@@ -0,0 +1,949 @@
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand};
+use serde_json::{json, Value};
+use sim_core::config::FileConfig;
+use sim_core::optimize::{Objective, Schedule, Target, Weights};
+use sim_core::output::{Style, DEFAULT_DECIMALS, MAX_DECIMALS};
+use sim_core::presets::{preset, PRESETS};
+use sim_core::rng::seed_from_str;
+use sim_core::sobol::MIN_SOBOL_SAMPLES;
+use sim_core::sweep::ParamRange;
+use sim_core::{
+    AggregateOptions, Coefficients, Metric, Model, Noise, ParamBounds, Params, RngKind, RunConfig,
+    SimConfig, SuccessRule, DEFAULT_CI_LEVEL, DEFAULT_GAUSSIAN_SIGMA, DEFAULT_HISTOGRAM_BINS,
+    DEFAULT_MODE_PROMINENCE, DEFAULT_OUTLIER_K, DEFAULT_SUCCESS_THRESHOLD,
+};
+
+use crate::log::{self, LogFormat, LogLevel, Severity};
+
+/// Monte Carlo episode simulator for the overseer policy parameters.
+///
+/// Every argument is validated: an unknown or misspelled flag is an error
+/// (exit status 2, naming the closest real flag), never silently ignored.
+#[derive(Debug, Parser)]
+#[command(name = "sim-core", version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Flags given without a subcommand run `run`.
+    #[command(flatten)]
+    pub run: RunArgs,
+}
+
+// Built once per process, so the size gap between `Run` and the
+// file-reading subcommands costs nothing worth boxing for.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run episodes at one parameter point (the default)
+    Run(RunArgs),
+    /// Run every point of a parameter grid, one NDJSON line per cell
+    Sweep(SweepArgs),
+    /// Search `[0,1]^4` for the parameters that maximize an aggregate metric
+    Optimize(OptimizeArgs),
+    /// Test whether two parameter sets differ in objective_rate and mean_elapsed_s
+    Compare(CompareArgs),
+    /// Estimate the partial derivatives of a metric at the flag parameters
+    Gradient(GradientArgs),
+    /// Sample parameter sets and keep the unlock_rate / stability_rate Pareto front
+    Pareto(ParetoArgs),
+    /// Estimate first-order Sobol indices of a metric over the parameter space
+    Sobol(SobolArgs),
+    /// Time --episodes single-threaded episodes and report episodes/sec on stderr
+    Bench(BenchArgs),
+    /// Show how every aggregate metric moved between two saved outputs
+    Diff(DiffArgs),
+    /// Combine the aggregates of saved partial runs into one
+    Merge(MergeArgs),
+}
+
+/// Stderr verbosity and format, on every subcommand.
+#[derive(Debug, Args)]
+pub struct LogArgs {
+    /// Print no warnings, progress or summary lines on stderr; errors still print
+    #[arg(short = 'q', long, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Also print the resolved parameters and seed, and the wall time, to stderr
+    #[arg(short = 'v', long)]
+    pub verbose: bool,
+    /// Write stderr diagnostics as plain text or as one JSON object per line
+    #[arg(long, value_name = "FORMAT", default_value = "text", value_parser = ["text", "json"])]
+    pub log_format: String,
+}
+
+impl LogArgs {
+    pub fn format(&self) -> LogFormat {
+        LogFormat::parse(&self.log_format).expect("clap restricts --log-format")
+    }
+
+    pub fn level(&self) -> LogLevel {
+        match (self.quiet, self.verbose) {
+            (true, _) => LogLevel::Quiet,
+            (_, true) => LogLevel::Verbose,
+            _ => LogLevel::Normal,
+        }
+    }
+}
+
+/// Settings shared by every subcommand: what to simulate and where to write.
+#[derive(Debug, Args)]
+pub struct CommonArgs {
+    /// Episodes per evaluation [default: 10, or the --config value]
+    #[arg(short = 'n', long, value_parser = positive)]
+    pub episodes: Option<usize>,
+    /// Instead of a fixed count, run until the --ci-level Wilson interval for
+    /// objective_rate has at most this half-width (checked every 1000 episodes)
+    #[arg(long, value_name = "HALF_WIDTH", conflicts_with = "episodes", value_parser = positive_f64)]
+    pub target_ci: Option<f64>,
+    /// Instead of a fixed count, run until this much wall time has passed
+    /// (the clock is read every 1024 episodes)
+    #[arg(long, value_name = "T", conflicts_with = "episodes", value_parser = duration_secs)]
+    pub time_budget_secs: Option<f64>,
+    /// Instead of a fixed count, run until N episodes have completed the
+    /// objective (inverse sampling)
+    #[arg(long, value_name = "N", conflicts_with = "episodes", value_parser = positive)]
+    pub until_successes: Option<usize>,
+    /// Episode cap for --target-ci, --time-budget-secs and --until-successes
+    #[arg(long, default_value_t = 10_000_000, value_parser = positive)]
+    pub max_episodes: usize,
+    /// Base seed [default: 1, or the --config value]
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Derive the seed from a label (64-bit FNV-1a); an explicit --seed wins
+    #[arg(long, value_name = "LABEL")]
+    pub seed_str: Option<String>,
+    /// Aggression parameter within --param-min..--param-max [default: 0.5, or the --config value]
+    #[arg(short = 'a', long, allow_negative_numbers = true, value_parser = finite_f64)]
+    pub aggression: Option<f64>,
+    /// Greed parameter within --param-min..--param-max [default: 0.5, or the --config value]
+    #[arg(short = 'g', long, allow_negative_numbers = true, value_parser = finite_f64)]
+    pub greed: Option<f64>,
+    /// Safety parameter within --param-min..--param-max [default: 0.5, or the --config value]
+    #[arg(short = 's', long, allow_negative_numbers = true, value_parser = finite_f64)]
+    pub safety: Option<f64>,
+    /// Focus parameter within --param-min..--param-max [default: 0.5, or the --config value]
+    #[arg(short = 'f', long, allow_negative_numbers = true, value_parser = finite_f64)]
+    pub focus: Option<f64>,
+    /// Lower bound every parameter is checked against or clamped to
+    #[arg(long, value_name = "MIN", allow_negative_numbers = true, default_value_t = ParamBounds::UNIT.min, value_parser = finite_f64)]
+    pub param_min: f64,
+    /// Upper bound every parameter is checked against or clamped to
+    #[arg(long, value_name = "MAX", allow_negative_numbers = true, default_value_t = ParamBounds::UNIT.max, value_parser = finite_f64)]
+    pub param_max: f64,
+    /// Start from a built-in profile (aggressive 0.9/0.8/0.2/0.6, balanced 0.5
+    /// each, cautious 0.2/0.3/0.9/0.5); parameter flags override single values
+    /// and the profile replaces the --config values
+    #[arg(long, value_name = "NAME", value_parser = PRESETS.map(|(name, _)| name))]
+    pub preset: Option<String>,
+    /// Read {aggression, greed, safety, focus} as one JSON object from stdin
+    /// (clamped to the bounds); replaces the parameter flags and the --config values
+    #[arg(long, conflicts_with_all = ["aggression", "greed", "safety", "focus", "preset"])]
+    pub params_stdin: bool,
+    /// TOML file with any of aggression, greed, safety, focus, episodes, seed;
+    /// flags win over the file
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Shape of the per-term perturbations
+    #[arg(long, default_value = "uniform", value_parser = ["uniform", "gaussian"])]
+    pub noise: String,
+    /// Gaussian standard deviation as a fraction of each range's half-width
+    #[arg(long, default_value_t = DEFAULT_GAUSSIAN_SIGMA, value_parser = non_negative_f64)]
+    pub noise_sigma: f64,
+    /// Use portable ln/cos for Gaussian noise so output is bit-identical on every platform
+    #[arg(long)]
+    pub deterministic: bool,
+    /// How objective_complete is decided: the Bernoulli draw, the
+    /// --success-unlock/--success-stability thresholds, or both
+    #[arg(long, default_value = "bernoulli", value_parser = ["bernoulli", "threshold", "and"])]
+    pub success_rule: String,
+    /// Minimum unlock_rate for the threshold and and success rules
+    #[arg(long, value_name = "T", default_value_t = DEFAULT_SUCCESS_THRESHOLD, value_parser = unit_interval)]
+    pub success_unlock: f64,
+    /// Minimum stability for the threshold and and success rules
+    #[arg(long, value_name = "S", default_value_t = DEFAULT_SUCCESS_THRESHOLD, value_parser = unit_interval)]
+    pub success_stability: f64,
+    /// Steps per episode; unlock accumulates and stability can erode from
+    /// step to step, and 1 is the one-shot model
+    #[arg(long, default_value_t = 1, value_parser = positive_u32)]
+    pub steps: u32,
+    /// Random number generator
+    #[arg(long, default_value = "lcg", value_parser = ["lcg", "pcg"])]
+    pub rng: String,
+    /// Worker threads; output is reproducible per thread count (a --seeds sweep runs one seed per worker and ignores it)
+    #[arg(long, default_value_t = 1, value_parser = positive)]
+    pub threads: usize,
+    /// Seed episode i from episode_seed(seed, i) instead of a shared stream
+    #[arg(long)]
+    pub independent_seeds: bool,
+    /// Run episodes in pairs whose second half mirrors the first's uniforms
+    #[arg(long)]
+    pub antithetic: bool,
+    /// Record each episode's wall-clock compute time as compute_ns (slows the run)
+    #[arg(long)]
+    pub time_episodes: bool,
+    /// Episodes run and discarded before the kept ones
+    #[arg(long, default_value_t = 0)]
+    pub warmup: usize,
+    /// Bootstrap resamples for an objective_rate interval (0 disables it)
+    #[arg(long, default_value_t = 0)]
+    pub bootstrap: usize,
+    /// Add a histogram of this metric to the aggregate
+    #[arg(long, value_parser = ["elapsed", "unlock_rate", "stability"])]
+    pub histogram: Option<String>,
+    /// Histogram buckets
+    #[arg(long, default_value_t = DEFAULT_HISTOGRAM_BINS, value_parser = positive)]
+    pub bins: usize,
+    /// Report histogram peaks at least this prominent, as a fraction of the tallest bin
+    #[arg(long, value_name = "P", default_value_t = DEFAULT_MODE_PROMINENCE, value_parser = unit_interval)]
+    pub mode_prominence: f64,
+    /// Add covariance and correlation matrices of unlock_rate, stability and elapsed_s to the aggregate
+    #[arg(long)]
+    pub covariance: bool,
+    /// Coverage of the objective_rate Wilson interval
+    #[arg(long, default_value_t = DEFAULT_CI_LEVEL, value_parser = open_unit_interval)]
+    pub ci_level: f64,
+    /// Fraction of sorted elapsed_s dropped from each end for elapsed_trimmed_mean
+    #[arg(long, default_value_t = 0.0, value_parser = trim_fraction)]
+    pub trim: f64,
+    /// Clamp unlock_rate, stability and elapsed_s to their P and 1 - P percentiles
+    /// before summarizing them (0 disables it)
+    #[arg(long, value_name = "P", default_value_t = 0.0, value_parser = trim_fraction, conflicts_with = "antithetic")]
+    pub winsorize: f64,
+    /// Flag episodes whose elapsed_s is more than K standard deviations from the mean
+    #[arg(long, value_name = "K", default_value_t = DEFAULT_OUTLIER_K, value_parser = positive_f64)]
+    pub outlier_k: f64,
+    /// Still run every episode, but keep only those with stability >= T in the
+    /// rows and the aggregate, which counts the rest as filtered_out
+    #[arg(long, value_name = "T", value_parser = unit_interval)]
+    pub min_stability: Option<f64>,
+    /// Write the result to this file (created or truncated) instead of stdout
+    #[arg(short = 'o', long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+    /// Gzip-compress the output; implied when an output path ends in .gz
+    #[arg(long)]
+    pub gzip: bool,
+    /// Indent JSON output
+    #[arg(long)]
+    pub pretty: bool,
+    /// Decimals kept on every float in JSON and CSV output
+    #[arg(long, value_name = "D", default_value_t = DEFAULT_DECIMALS, value_parser = clap::value_parser!(u32).range(0..=MAX_DECIMALS as i64))]
+    pub precision: u32,
+    #[command(flatten)]
+    pub log: LogArgs,
+    /// Accepted so existing scripts keep working; strict parsing is always on
+    #[arg(long, hide = true)]
+    pub strict: bool,
+    #[command(flatten)]
+    pub coefficients: CoefficientArgs,
+}
+
+/// Model coefficient overrides; the defaults are the built-in model.
+#[derive(Debug, Args)]
+#[command(next_help_heading = "Model coefficients")]
+pub struct CoefficientArgs {
+    /// Unlock weight of aggression
+    #[arg(long = "coeff-unlock-aggression", value_name = "W", default_value_t = Coefficients::DEFAULT.unlock_aggression, value_parser = non_negative_f64)]
+    pub unlock_aggression: f64,
+    /// Unlock weight of greed
+    #[arg(long = "coeff-unlock-greed", value_name = "W", default_value_t = Coefficients::DEFAULT.unlock_greed, value_parser = non_negative_f64)]
+    pub unlock_greed: f64,
+    /// Unlock weight of focus
+    #[arg(long = "coeff-unlock-focus", value_name = "W", default_value_t = Coefficients::DEFAULT.unlock_focus, value_parser = non_negative_f64)]
+    pub unlock_focus: f64,
+    /// Unlock penalty per unit of safety above the threshold
+    #[arg(long = "coeff-unlock-safety-penalty", value_name = "W", default_value_t = Coefficients::DEFAULT.unlock_safety_penalty, value_parser = non_negative_f64)]
+    pub unlock_safety_penalty: f64,
+    /// Safety above which unlock is penalized
+    #[arg(long = "coeff-unlock-safety-threshold", value_name = "W", default_value_t = Coefficients::DEFAULT.unlock_safety_threshold, value_parser = non_negative_f64)]
+    pub unlock_safety_threshold: f64,
+    /// Stability weight of safety
+    #[arg(long = "coeff-stability-safety", value_name = "W", default_value_t = Coefficients::DEFAULT.stability_safety, value_parser = non_negative_f64)]
+    pub stability_safety: f64,
+    /// Stability weight of focus
+    #[arg(long = "coeff-stability-focus", value_name = "W", default_value_t = Coefficients::DEFAULT.stability_focus, value_parser = non_negative_f64)]
+    pub stability_focus: f64,
+    /// Stability penalty per unit of |aggression - greed|
+    #[arg(long = "coeff-stability-imbalance", value_name = "W", default_value_t = Coefficients::DEFAULT.stability_imbalance, value_parser = non_negative_f64)]
+    pub stability_imbalance: f64,
+    /// Stability penalty per unit of aggression above the threshold
+    #[arg(long = "coeff-stability-aggression-penalty", value_name = "W", default_value_t = Coefficients::DEFAULT.stability_aggression_penalty, value_parser = non_negative_f64)]
+    pub stability_aggression_penalty: f64,
+    /// Aggression above which stability is penalized
+    #[arg(long = "coeff-stability-aggression-threshold", value_name = "W", default_value_t = Coefficients::DEFAULT.stability_aggression_threshold, value_parser = non_negative_f64)]
+    pub stability_aggression_threshold: f64,
+    /// Objective success probability at zero unlock and stability
+    #[arg(long = "coeff-objective-base", value_name = "W", default_value_t = Coefficients::DEFAULT.objective_base, value_parser = non_negative_f64)]
+    pub objective_base: f64,
+    /// Objective probability weight of unlock_rate
+    #[arg(long = "coeff-objective-unlock", value_name = "W", default_value_t = Coefficients::DEFAULT.objective_unlock, value_parser = non_negative_f64)]
+    pub objective_unlock: f64,
+    /// Objective probability weight of stability
+    #[arg(long = "coeff-objective-stability", value_name = "W", default_value_t = Coefficients::DEFAULT.objective_stability, value_parser = non_negative_f64)]
+    pub objective_stability: f64,
+    /// Elapsed seconds at zero unlock rate, before noise
+    #[arg(long = "coeff-elapsed-base", value_name = "W", default_value_t = Coefficients::DEFAULT.elapsed_base, value_parser = non_negative_f64)]
+    pub elapsed_base: f64,
+    /// Fraction of elapsed_base saved at full unlock rate
+    #[arg(long = "coeff-elapsed-unlock", value_name = "W", default_value_t = Coefficients::DEFAULT.elapsed_unlock, value_parser = non_negative_f64)]
+    pub elapsed_unlock: f64,
+    /// Share of unlock rate and stability carried from one step to the next
+    #[arg(long = "coeff-step-decay", value_name = "W", default_value_t = Coefficients::DEFAULT.step_decay, value_parser = non_negative_f64)]
+    pub step_decay: f64,
+    /// Stability lost per unit of unlock rate gained in a step
+    #[arg(long = "coeff-step-strain", value_name = "W", default_value_t = Coefficients::DEFAULT.step_strain, value_parser = non_negative_f64)]
+    pub step_strain: f64,
+    /// Half-width of the uniform noise on each unlock draw
+    #[arg(long, value_name = "H", default_value_t = Coefficients::DEFAULT.unlock_noise, value_parser = non_negative_f64)]
+    pub unlock_noise: f64,
+    /// Half-width of the uniform noise on each stability draw
+    #[arg(long, value_name = "H", default_value_t = Coefficients::DEFAULT.stability_noise, value_parser = non_negative_f64)]
+    pub stability_noise: f64,
+    /// Half-width of the relative elapsed_s noise, centred on -0.015
+    #[arg(long, value_name = "H", default_value_t = Coefficients::DEFAULT.elapsed_noise, value_parser = non_negative_f64)]
+    pub elapsed_noise: f64,
+}
+
+impl CoefficientArgs {
+    pub fn coefficients(&self) -> Coefficients {
+        Coefficients {
+            unlock_aggression: self.unlock_aggression,
+            unlock_greed: self.unlock_greed,
+            unlock_focus: self.unlock_focus,
+            unlock_safety_penalty: self.unlock_safety_penalty,
+            unlock_safety_threshold: self.unlock_safety_threshold,
+            stability_safety: self.stability_safety,
+            stability_focus: self.stability_focus,
+            stability_imbalance: self.stability_imbalance,
+            stability_aggression_penalty: self.stability_aggression_penalty,
+            stability_aggression_threshold: self.stability_aggression_threshold,
+            objective_base: self.objective_base,
+            objective_unlock: self.objective_unlock,
+            objective_stability: self.objective_stability,
+            elapsed_base: self.elapsed_base,
+            elapsed_unlock: self.elapsed_unlock,
+            step_decay: self.step_decay,
+            step_strain: self.step_strain,
+            unlock_noise: self.unlock_noise,
+            stability_noise: self.stability_noise,
+            elapsed_noise: self.elapsed_noise,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+    /// json: one object; csv: rows on stdout, aggregate on stderr;
+    /// ndjson: one episode per line as it finishes, then the aggregate
+    #[arg(long, default_value = "json", value_parser = ["json", "csv", "ndjson"])]
+    pub format: String,
+    /// Unit of elapsed_s and the elapsed aggregate fields (seconds, minutes
+    /// or hours); the model itself always runs in seconds
+    #[arg(long, value_name = "UNIT", default_value = "s", value_parser = ["s", "m", "h"], conflicts_with_all = ["params_file", "seeds", "seeds_file"])]
+    pub time_unit: String,
+    /// Also record when the run started in `meta` as an ISO-8601 UTC
+    /// `timestamp`; off by default so reruns stay byte-identical
+    #[arg(long, conflicts_with_all = ["params_file", "seeds", "seeds_file"])]
+    pub timestamp: bool,
+    /// Keep no rows and print only the aggregate
+    #[arg(long)]
+    pub summary_only: bool,
+    /// Report progress on stderr for runs of 100k episodes or more
+    #[arg(long)]
+    pub progress: bool,
+    /// Run each named set in a JSON array of {name, aggression, greed, safety, focus}
+    #[arg(long, value_name = "FILE")]
+    pub params_file: Option<PathBuf>,
+    /// Rerun with seeds seed, seed+1, ... and report the spread of objective_rate
+    #[arg(long, value_name = "N", value_parser = positive, conflicts_with = "params_file")]
+    pub seeds: Option<usize>,
+    /// Like --seeds, but with the seeds read from FILE, one per line; blank
+    /// lines and # comments are skipped
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["params_file", "seeds"])]
+    pub seeds_file: Option<PathBuf>,
+    /// Also write the full JSON output to this file; combines with --csv-out
+    /// so both come from one run
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["output", "format", "summary_only", "params_file", "seeds", "seeds_file"])]
+    pub json_out: Option<PathBuf>,
+    /// Write the episode rows as CSV to this file; combines with --json-out
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["output", "format", "summary_only", "params_file", "seeds", "seeds_file"])]
+    pub csv_out: Option<PathBuf>,
+    /// Also write the final aggregate alone to this file, as --summary-only
+    /// would print it, wherever the episodes go
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["params_file", "seeds", "seeds_file", "replay"])]
+    pub aggregate_out: Option<PathBuf>,
+    /// After the run, print a text histogram of this metric to stderr in
+    /// --bins buckets, binned like --histogram
+    #[arg(long, value_parser = ["elapsed", "unlock_rate", "stability"], conflicts_with_all = ["params_file", "seeds", "seeds_file"])]
+    pub chart: Option<String>,
+    /// After the run, print the main aggregate fields as a table on stderr,
+    /// marking objective_rate against --fail-under
+    #[arg(long, conflicts_with_all = ["params_file", "seeds", "seeds_file", "replay"])]
+    pub table: bool,
+    /// Colour the --table: auto colours only when stderr is a terminal and
+    /// NO_COLOR is unset
+    #[arg(long, value_name = "WHEN", default_value = "auto", value_parser = ["auto", "always", "never"])]
+    pub color: String,
+    /// Exit with status 2 if the final objective_rate is below this
+    #[arg(long, value_name = "RATE", conflicts_with_all = ["params_file", "seeds", "seeds_file"])]
+    pub fail_under: Option<f64>,
+    /// Print the aggregate so far as an NDJSON line every K episodes, then
+    /// the full aggregate as the last line
+    #[arg(long, value_name = "K", value_parser = positive, conflicts_with_all = ["format", "summary_only", "params_file", "seeds", "seeds_file", "json_out", "csv_out", "winsorize"])]
+    pub emit_every: Option<usize>,
+    /// Rerun only kept episode INDEX (0-based, as in the ndjson lines) and
+    /// print it as one JSON object
+    #[arg(long, value_name = "INDEX", conflicts_with_all = ["format", "summary_only", "params_file", "seeds", "seeds_file", "json_out", "csv_out", "chart", "fail_under", "dump_rng", "emit_every"])]
+    pub replay: Option<usize>,
+    /// After a run that finishes, write the final LCG state to this file
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["params_file", "seeds", "seeds_file"])]
+    pub dump_rng: Option<PathBuf>,
+    /// Continue the LCG stream from a --dump-rng file instead of seeding it
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["params_file", "seeds", "seeds_file"])]
+    pub resume_rng: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct SweepArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+    /// min:max:step (unset axes stay at their parameter value)
+    #[arg(long, value_parser = ParamRange::parse)]
+    pub aggression_range: Option<ParamRange>,
+    #[arg(long, value_parser = ParamRange::parse)]
+    pub greed_range: Option<ParamRange>,
+    #[arg(long, value_parser = ParamRange::parse)]
+    pub safety_range: Option<ParamRange>,
+    #[arg(long, value_parser = ParamRange::parse)]
+    pub focus_range: Option<ParamRange>,
+}
+
+#[derive(Debug, Args)]
+pub struct OptimizeArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+    /// Aggregate metric to maximize
+    #[arg(long, default_value = "objective_rate",
+          value_parser = ["objective_rate", "stability_rate", "unlock_rate"])]
+    pub target: String,
+    /// Maximize a weighted sum instead of --target, as name=weight pairs
+    /// such as objective_rate=1,mean_elapsed=-0.5 (mean_elapsed is
+    /// normalized to [0,1] over the 80-2000 s clamp range)
+    #[arg(long, value_name = "WEIGHTS", value_parser = Weights::parse, conflicts_with = "target")]
+    pub objective_weights: Option<Weights>,
+    /// random: uniform samples of [0,1]^4; hillclimb: greedy steps from the flag parameters;
+    /// nelder-mead: a simplex search from the flag parameters; anneal: simulated annealing
+    /// from the flag parameters
+    #[arg(long, default_value = "random", value_parser = ["random", "hillclimb", "nelder-mead", "anneal"])]
+    pub method: String,
+    /// Candidate points for the random method; halton is deterministic and ignores the seed,
+    /// lhs stratifies every axis into --trials slices
+    #[arg(long, default_value = "random", value_parser = ["random", "halton", "lhs"])]
+    pub sampler: String,
+    /// Candidates for the random method
+    #[arg(long, default_value_t = 100, value_parser = positive)]
+    pub trials: usize,
+    /// Per-axis hill-climb step, the edge of the initial Nelder-Mead simplex, or the
+    /// largest per-axis annealing move
+    #[arg(long, default_value_t = 0.05, value_parser = positive_f64)]
+    pub step: f64,
+    /// Hill-climb or Nelder-Mead iteration cap, or the annealing proposal count
+    #[arg(long, default_value_t = 100, value_parser = positive)]
+    pub max_iters: usize,
+    /// How the annealing temperature cools over --max-iters
+    #[arg(long, default_value = "exponential", value_parser = ["exponential", "linear", "log"])]
+    pub schedule: String,
+    /// Starting annealing temperature, in score units
+    #[arg(long, default_value_t = 0.05, value_parser = positive_f64)]
+    pub temperature: f64,
+}
+
+#[derive(Debug, Args)]
+pub struct GradientArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+    /// Aggregate metric to differentiate
+    #[arg(long, default_value = "objective_rate",
+          value_parser = ["objective_rate", "stability_rate", "unlock_rate"])]
+    pub target: String,
+    /// Differentiate a weighted sum instead of --target, written as for
+    /// optimize --objective-weights
+    #[arg(long, value_name = "WEIGHTS", value_parser = Weights::parse, conflicts_with = "target")]
+    pub objective_weights: Option<Weights>,
+    /// Central-difference half-step on each axis
+    #[arg(long, default_value_t = 0.02, value_parser = positive_f64)]
+    pub epsilon: f64,
+}
+
+#[derive(Debug, Args)]
+pub struct ParetoArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+    /// Candidate points; halton is deterministic and ignores the seed, lhs
+    /// stratifies every axis into --trials slices
+    #[arg(long, default_value = "random", value_parser = ["random", "halton", "lhs"])]
+    pub sampler: String,
+    /// Parameter sets to evaluate
+    #[arg(long, default_value_t = 100, value_parser = positive)]
+    pub trials: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct SobolArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+    /// Aggregate metric to decompose
+    #[arg(long, default_value = "objective_rate",
+          value_parser = ["objective_rate", "stability_rate", "unlock_rate"])]
+    pub target: String,
+    /// Decompose a weighted sum instead of --target, written as for
+    /// optimize --objective-weights
+    #[arg(long, value_name = "WEIGHTS", value_parser = Weights::parse, conflicts_with = "target")]
+    pub objective_weights: Option<Weights>,
+    /// Rows of each Saltelli base matrix, at least 16; the run makes six times
+    /// as many evaluations
+    #[arg(long, default_value_t = 256, value_parser = sobol_samples)]
+    pub samples: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+}
+
+/// Reads two outputs back rather than simulating, so it takes no shared flags.
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// Earlier json, --summary-only or ndjson output (optionally .gz)
+    pub old: PathBuf,
+    /// Later output in any of the same forms
+    pub new: PathBuf,
+    /// Flag metrics whose absolute delta exceeds this
+    #[arg(long, alias = "diff-threshold", default_value_t = 0.0, value_parser = non_negative_f64)]
+    pub threshold: f64,
+    /// Write the result to this file (created or truncated) instead of stdout
+    #[arg(short = 'o', long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+    /// Indent JSON output
+    #[arg(long)]
+    pub pretty: bool,
+    /// Decimals kept on every float in JSON and CSV output
+    #[arg(long, value_name = "D", default_value_t = DEFAULT_DECIMALS, value_parser = clap::value_parser!(u32).range(0..=MAX_DECIMALS as i64))]
+    pub precision: u32,
+    #[command(flatten)]
+    pub log: LogArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct MergeArgs {
+    /// Outputs of disjoint runs, e.g. one per machine, each in any form diff reads
+    #[arg(required = true, value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+    /// Coverage of the recomputed objective_rate Wilson interval
+    #[arg(long, default_value_t = DEFAULT_CI_LEVEL, value_parser = open_unit_interval)]
+    pub ci_level: f64,
+    /// Write the result to this file (created or truncated) instead of stdout
+    #[arg(short = 'o', long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+    /// Indent JSON output
+    #[arg(long)]
+    pub pretty: bool,
+    /// Decimals kept on every float in JSON and CSV output
+    #[arg(long, value_name = "D", default_value_t = DEFAULT_DECIMALS, value_parser = clap::value_parser!(u32).range(0..=MAX_DECIMALS as i64))]
+    pub precision: u32,
+    #[command(flatten)]
+    pub log: LogArgs,
+}
+
+impl Command {
+    /// The checks clap can't do alone because they depend on two flags.
+    pub fn check_bounds(&self) -> Result<(), String> {
+        match self {
+            Self::Run(args) => args.common.check_bounds(),
+            Self::Sweep(args) => args.common.check_bounds(),
+            Self::Optimize(args) => args.common.check_bounds(),
+            Self::Compare(args) => args.check_bounds(),
+            Self::Gradient(args) => args.common.check_bounds(),
+            Self::Pareto(args) => args.common.check_bounds(),
+            Self::Sobol(args) => args.common.check_bounds(),
+            Self::Bench(args) => args.common.check_bounds(),
+            Self::Diff(_) | Self::Merge(_) => Ok(()),
+        }
+    }
+
+    pub fn log(&self) -> &LogArgs {
+        match self {
+            Self::Run(args) => &args.common.log,
+            Self::Sweep(args) => &args.common.log,
+            Self::Optimize(args) => &args.common.log,
+            Self::Compare(args) => &args.common.log,
+            Self::Gradient(args) => &args.common.log,
+            Self::Pareto(args) => &args.common.log,
+            Self::Sobol(args) => &args.common.log,
+            Self::Bench(args) => &args.common.log,
+            Self::Diff(args) => &args.log,
+            Self::Merge(args) => &args.log,
+        }
+    }
+}
+
+/// Set A is the usual parameter flags; set B starts from A and takes any
+/// of `--b-config` and the `--b-*` flags on top.
+#[derive(Debug, Args)]
+pub struct CompareArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+    /// TOML file with set B's aggression, greed, safety and focus
+    #[arg(long, value_name = "FILE")]
+    pub b_config: Option<PathBuf>,
+    /// Set B aggression
+    #[arg(long, allow_negative_numbers = true, value_parser = finite_f64)]
+    pub b_aggression: Option<f64>,
+    /// Set B greed
+    #[arg(long, allow_negative_numbers = true, value_parser = finite_f64)]
+    pub b_greed: Option<f64>,
+    /// Set B safety
+    #[arg(long, allow_negative_numbers = true, value_parser = finite_f64)]
+    pub b_safety: Option<f64>,
+    /// Set B focus
+    #[arg(long, allow_negative_numbers = true, value_parser = finite_f64)]
+    pub b_focus: Option<f64>,
+}
+
+impl CompareArgs {
+    pub fn check_bounds(&self) -> Result<(), String> {
+        self.common.check_bounds()?;
+        check_within(
+            self.common.bounds()?,
+            [
+                ("--b-aggression", self.b_aggression),
+                ("--b-greed", self.b_greed),
+                ("--b-safety", self.b_safety),
+                ("--b-focus", self.b_focus),
+            ],
+        )
+    }
+
+    /// Set B's parameters: `--b-*` flags over `--b-config` over set A's.
+    pub fn b_params(&self, a: Params, bounds: ParamBounds) -> Result<Params, String> {
+        let file = match &self.b_config {
+            Some(path) => FileConfig::load(path)?,
+            None => FileConfig::default(),
+        };
+        let param = |flag: Option<f64>, from_file: Option<f64>, from_a: f64| {
+            flag.unwrap_or_else(|| from_file.map_or(from_a, |v| bounds.clamp(v)))
+        };
+        Ok(Params {
+            aggression: param(self.b_aggression, file.aggression, a.aggression),
+            greed: param(self.b_greed, file.greed, a.greed),
+            safety: param(self.b_safety, file.safety, a.safety),
+            focus: param(self.b_focus, file.focus, a.focus),
+        })
+    }
+}
+
+fn check_within(bounds: ParamBounds, flags: [(&str, Option<f64>); 4]) -> Result<(), String> {
+    for (flag, value) in flags {
+        if let Some(v) = value.filter(|v| !(bounds.min..=bounds.max).contains(v)) {
+            return Err(format!(
+                "{} must be within [{}, {}], got {}",
+                flag, bounds.min, bounds.max, v
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn finite_f64(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(v) if v.is_finite() => Ok(v),
+        _ => Err(format!("expected a number, got {:?}", text)),
+    }
+}
+
+fn unit_interval(text: &str) -> Result<f64, String> {
+    let v: f64 = text
+        .parse()
+        .map_err(|_| format!("expected a number, got {:?}", text))?;
+    if !(0.0..=1.0).contains(&v) {
+        return Err(format!("must be within [0, 1], got {}", v));
+    }
+    Ok(v)
+}
+
+fn open_unit_interval(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(v) if v > 0.0 && v < 1.0 => Ok(v),
+        _ => Err(format!(
+            "expected a number strictly between 0 and 1, got {:?}",
+            text
+        )),
+    }
+}
+
+fn trim_fraction(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(v) if (0.0..0.5).contains(&v) => Ok(v),
+        _ => Err(format!("expected a fraction in [0, 0.5), got {:?}", text)),
+    }
+}
+
+fn positive_f64(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(v) if v > 0.0 && v.is_finite() => Ok(v),
+        _ => Err(format!("expected a positive number, got {:?}", text)),
+    }
+}
+
+/// Positive seconds that fit in a `Duration`; `inf` and `1e30` don't.
+fn duration_secs(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(v) if v > 0.0 && Duration::try_from_secs_f64(v).is_ok() => Ok(v),
+        _ => Err(format!(
+            "expected a positive, finite number of seconds, got {:?}",
+            text
+        )),
+    }
+}
+
+fn non_negative_f64(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(v) if v >= 0.0 && v.is_finite() => Ok(v),
+        _ => Err(format!("expected a non-negative number, got {:?}", text)),
+    }
+}
+
+fn positive_u32(text: &str) -> Result<u32, String> {
+    match text.parse::<u32>() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => Err(format!("expected a positive integer, got {:?}", text)),
+    }
+}
+
+fn sobol_samples(text: &str) -> Result<usize, String> {
+    match text.parse::<usize>() {
+        Ok(n) if n >= MIN_SOBOL_SAMPLES => Ok(n),
+        _ => Err(format!(
+            "expected an integer of at least {}, got {:?}",
+            MIN_SOBOL_SAMPLES, text
+        )),
+    }
+}
+
+fn positive(text: &str) -> Result<usize, String> {
+    match text.parse::<usize>() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => Err(format!("expected a positive integer, got {:?}", text)),
+    }
+}
+
+/// `--objective-weights` if given, else `--target`.
+fn objective(target: &str, weights: Option<Weights>) -> Objective {
+    match weights {
+        Some(weights) => Objective::Weighted(weights),
+        None => Target::parse(target)
+            .expect("clap restricts --target")
+            .into(),
+    }
+}
+
+impl OptimizeArgs {
+    pub fn objective(&self) -> Objective {
+        objective(&self.target, self.objective_weights)
+    }
+
+    pub fn schedule(&self) -> Schedule {
+        Schedule::parse(&self.schedule).expect("clap restricts --schedule")
+    }
+}
+
+impl GradientArgs {
+    pub fn objective(&self) -> Objective {
+        objective(&self.target, self.objective_weights)
+    }
+}
+
+impl SobolArgs {
+    pub fn objective(&self) -> Objective {
+        objective(&self.target, self.objective_weights)
+    }
+}
+
+impl DiffArgs {
+    pub fn style(&self) -> Style {
+        Style {
+            pretty: self.pretty,
+            decimals: self.precision,
+        }
+    }
+}
+
+impl MergeArgs {
+    pub fn style(&self) -> Style {
+        Style {
+            pretty: self.pretty,
+            decimals: self.precision,
+        }
+    }
+}
+
+impl CommonArgs {
+    pub fn style(&self) -> Style {
+        Style {
+            pretty: self.pretty,
+            decimals: self.precision,
+        }
+    }
+
+    pub fn bounds(&self) -> Result<ParamBounds, String> {
+        ParamBounds::new(self.param_min, self.param_max)
+    }
+
+    /// Rejects bounds with `min >= max` and parameter flags outside them;
+    /// values from files and stdin are clamped instead.
+    pub fn check_bounds(&self) -> Result<(), String> {
+        let bounds = self.bounds()?;
+        check_within(
+            bounds,
+            [
+                ("--aggression", self.aggression),
+                ("--greed", self.greed),
+                ("--safety", self.safety),
+                ("--focus", self.focus),
+            ],
+        )
+    }
+
+    /// Resolves flags over the `--config` file over the built-in defaults.
+    pub fn config(&self) -> Result<RunConfig, String> {
+        let file = match &self.config {
+            Some(path) => FileConfig::load(path)?,
+            None => FileConfig::default(),
+        };
+        let early_stop = self.target_ci.is_some()
+            || self.time_budget_secs.is_some()
+            || self.until_successes.is_some();
+        let episodes = if early_stop {
+            self.max_episodes
+        } else {
+            self.episodes.unwrap_or(file.episodes.unwrap_or(10).max(1))
+        };
+        if self.seed_str.is_some() && self.seed.is_some() && self.log.level().warnings() {
+            log::warning(
+                "both --seed and --seed-str given; using --seed",
+                Value::Null,
+            );
+        }
+        let seed = self.seed.unwrap_or_else(|| match &self.seed_str {
+            Some(label) => seed_from_str(label),
+            None => file.seed.unwrap_or(1),
+        });
+        let preset = self
+            .preset
+            .as_deref()
+            .map(|name| preset(name).expect("clap restricts --preset"));
+        let bounds = self.bounds()?;
+        let param = |flag: Option<f64>, from_file: Option<f64>, pick: fn(&Params) -> f64| {
+            flag.unwrap_or_else(|| {
+                bounds.clamp(preset.as_ref().map_or(from_file.unwrap_or(0.5), pick))
+            })
+        };
+        let params = if self.params_stdin {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|err| format!("cannot read stdin: {}", err))?;
+            Params::from_json(&text)
+                .map_err(|err| format!("invalid params on stdin: {}", err))?
+                .clamped_to(bounds)
+        } else {
+            Params {
+                aggression: param(self.aggression, file.aggression, |p| p.aggression),
+                greed: param(self.greed, file.greed, |p| p.greed),
+                safety: param(self.safety, file.safety, |p| p.safety),
+                focus: param(self.focus, file.focus, |p| p.focus),
+            }
+        };
+        let noise = match self.noise.as_str() {
+            "gaussian" => Noise::Gaussian {
+                sigma: self.noise_sigma,
+            },
+            _ => Noise::Uniform,
+        };
+        let (unlock, stability) = (self.success_unlock, self.success_stability);
+        let success = match self.success_rule.as_str() {
+            "threshold" => SuccessRule::Threshold { unlock, stability },
+            "and" => SuccessRule::And { unlock, stability },
+            _ => SuccessRule::Bernoulli,
+        };
+        let mut builder = SimConfig::new()
+            .params(params)
+            .seed(seed)
+            .episodes(episodes)
+            .warmup(self.warmup)
+            .threads(self.threads)
+            .independent_seeds(self.independent_seeds)
+            .model(Model {
+                noise,
+                coefficients: self.coefficients.coefficients(),
+                deterministic: self.deterministic,
+                success,
+                steps: self.steps,
+            })
+            .rng(RngKind::parse(&self.rng).unwrap_or_default())
+            .options(AggregateOptions {
+                bootstrap: self.bootstrap,
+                histogram: self.histogram.as_deref().and_then(Metric::parse),
+                bins: self.bins,
+                mode_prominence: self.mode_prominence,
+                ci_level: self.ci_level,
+                trim: self.trim,
+                outlier_k: self.outlier_k,
+                covariance: self.covariance,
+                winsorize: self.winsorize,
+            })
+            .antithetic(self.antithetic)
+            .bounds(bounds)
+            .time_episodes(self.time_episodes);
+        if let Some(half_width) = self.target_ci {
+            builder = builder.target_ci(half_width);
+        }
+        if let Some(secs) = self.time_budget_secs {
+            builder = builder.time_budget(Duration::from_secs_f64(secs));
+        }
+        if let Some(successes) = self.until_successes {
+            builder = builder.until_successes(successes);
+        }
+        if let Some(floor) = self.min_stability {
+            builder = builder.min_stability(floor);
+        }
+        let config = builder.build()?.run_config();
+        if self.log.level().verbose() {
+            let msg = format!(
+                "seed {}, {} episodes, aggression {}, greed {}, safety {}, focus {}, rng {}, threads {}",
+                seed,
+                episodes,
+                params.aggression,
+                params.greed,
+                params.safety,
+                params.focus,
+                self.rng,
+                self.threads
+            );
+            let fields = json!({
+                "seed": seed,
+                "episodes": episodes,
+                "params": params,
+                "rng": self.rng,
+                "threads": self.threads,
+            });
+            log::emit(Severity::Debug, "verbose", &msg, fields);
+        }
+        Ok(config)
+    }
+}
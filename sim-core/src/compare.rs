@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::run::stream_seed;
+use crate::stats::{normal_cdf, student_t_p_value};
+use crate::{Aggregate, Params, RunConfig};
+
+/// One side of a comparison: its parameters and streamed aggregate.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    pub params: Params,
+    pub aggregate: Aggregate,
+}
+
+/// Pooled two-proportion z-test of `a.objective_rate - b.objective_rate`.
+/// `z` and `p_value` are `None` when both groups are all hits or all misses.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ZTest {
+    pub difference: f64,
+    pub z: Option<f64>,
+    pub p_value: Option<f64>,
+}
+
+/// Welch's unequal-variance t-test of `a.mean_elapsed_s - b.mean_elapsed_s`.
+/// The statistics are `None` when a group has fewer than two episodes or
+/// both variances are zero.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WelchTest {
+    pub difference: f64,
+    pub t: Option<f64>,
+    pub df: Option<f64>,
+    pub p_value: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Comparison {
+    pub a: Group,
+    pub b: Group,
+    pub objective_rate: ZTest,
+    pub mean_elapsed_s: WelchTest,
+}
+
+/// Streams `base.episodes` episodes at `a` and at `b`. Set `a` runs on the
+/// base seed and `b` on `stream_seed(seed, 1)`, so the groups are independent.
+pub fn compare(base: &RunConfig, a: Params, b: Params) -> Comparison {
+    let side = |params: Params, stream: u64| {
+        let config = RunConfig {
+            params,
+            seed: stream_seed(base.seed, stream),
+            ..*base
+        };
+        Group {
+            params,
+            aggregate: config.stream(|_| {}),
+        }
+    };
+    let a = side(a, 0);
+    let b = side(b, 1);
+    Comparison {
+        objective_rate: z_test(&a.aggregate, &b.aggregate),
+        mean_elapsed_s: welch_test(&a.aggregate, &b.aggregate),
+        a,
+        b,
+    }
+}
+
+pub fn z_test(a: &Aggregate, b: &Aggregate) -> ZTest {
+    let (na, nb) = (a.episodes as f64, b.episodes as f64);
+    let difference = a.objective_rate - b.objective_rate;
+    let pooled = (a.objective_rate * na + b.objective_rate * nb) / (na + nb);
+    let se = (pooled * (1.0 - pooled) * (1.0 / na + 1.0 / nb)).sqrt();
+    let z = (se > 0.0).then(|| difference / se);
+    ZTest {
+        difference,
+        z,
+        p_value: z.map(|z| 2.0 * (1.0 - normal_cdf(z.abs()))),
+    }
+}
+
+pub fn welch_test(a: &Aggregate, b: &Aggregate) -> WelchTest {
+    let difference = a.mean_elapsed_s - b.mean_elapsed_s;
+    let undefined = WelchTest {
+        difference,
+        t: None,
+        df: None,
+        p_value: None,
+    };
+    if a.episodes < 2 || b.episodes < 2 {
+        return undefined;
+    }
+    let (na, nb) = (a.episodes as f64, b.episodes as f64);
+    let (va, vb) = (a.elapsed_var / na, b.elapsed_var / nb);
+    if va + vb <= 0.0 {
+        return undefined;
+    }
+    let t = difference / (va + vb).sqrt();
+    let df = (va + vb).powi(2) / (va * va / (na - 1.0) + vb * vb / (nb - 1.0));
+    WelchTest {
+        difference,
+        t: Some(t),
+        df: Some(df),
+        p_value: Some(student_t_p_value(t, df)),
+    }
+}
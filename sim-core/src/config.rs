@@ -0,0 +1,29 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// Run settings read from `--config <file.toml>`. Every key is optional;
+/// command-line flags override whatever the file sets.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub aggression: Option<f64>,
+    pub greed: Option<f64>,
+    pub safety: Option<f64>,
+    pub focus: Option<f64>,
+    pub episodes: Option<usize>,
+    pub seed: Option<u64>,
+}
+
+impl FileConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read config {}: {}", path.display(), err))?;
+        Self::parse(&text).map_err(|err| format!("invalid config {}: {}", path.display(), err))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::Aggregate;
+
+/// One aggregate metric in both runs. A side is `None` when that run has no
+/// value for it (`null` or an option that was off); `delta` is `new - old`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MetricDiff {
+    pub metric: String,
+    pub old: Option<f64>,
+    pub new: Option<f64>,
+    pub delta: Option<f64>,
+    /// `|delta|` is above the threshold.
+    pub flagged: bool,
+}
+
+/// Every top-level numeric metric of either aggregate, in field order.
+/// Nested blocks (`histogram`, `antithetic`) are left out.
+pub fn diff(old: &Aggregate, new: &Aggregate, threshold: f64) -> Vec<MetricDiff> {
+    let fields = |aggregate: &Aggregate| match serde_json::to_value(aggregate) {
+        Ok(Value::Object(map)) => map,
+        _ => unreachable!("an aggregate serializes to an object"),
+    };
+    let (old, new) = (fields(old), fields(new));
+    let mut names: Vec<&String> = old.keys().collect();
+    names.extend(new.keys().filter(|name| !old.contains_key(*name)));
+    names
+        .into_iter()
+        .filter(|name| {
+            let scalar = |map: &serde_json::Map<String, Value>| {
+                map.get(*name)
+                    .is_some_and(|value| value.is_number() || value.is_null())
+            };
+            scalar(&old) || scalar(&new)
+        })
+        .map(|name| {
+            let old = old.get(name).and_then(Value::as_f64);
+            let new = new.get(name).and_then(Value::as_f64);
+            let delta = old.zip(new).map(|(old, new)| new - old);
+            MetricDiff {
+                metric: name.clone(),
+                old,
+                new,
+                delta,
+                flagged: delta.is_some_and(|delta| delta.abs() > threshold),
+            }
+        })
+        .collect()
+}
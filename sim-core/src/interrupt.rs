@@ -0,0 +1,43 @@
+//! SIGINT handling without a dependency: the handler only raises a flag that
+//! the episode loop polls, so an interrupted run can still print what it has.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Raised by the first Ctrl-C.
+pub static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Exit status of an interrupted run (128 + SIGINT, as shells report it).
+pub const EXIT_INTERRUPTED: u8 = 130;
+
+#[cfg(unix)]
+mod sys {
+    use std::os::raw::c_int;
+
+    pub const SIGINT: c_int = 2;
+
+    extern "C" {
+        pub fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+        pub fn _exit(status: c_int) -> !;
+    }
+}
+
+/// A second Ctrl-C exits at once, for when the partial output isn't wanted.
+#[cfg(unix)]
+extern "C" fn on_sigint(_: std::os::raw::c_int) {
+    if INTERRUPTED.swap(true, Ordering::Relaxed) {
+        // SAFETY: `_exit` is async-signal-safe.
+        unsafe { sys::_exit(EXIT_INTERRUPTED.into()) }
+    }
+}
+
+pub fn install() {
+    #[cfg(unix)]
+    // SAFETY: the handler only touches an atomic and calls `_exit`.
+    unsafe {
+        sys::signal(sys::SIGINT, on_sigint);
+    }
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
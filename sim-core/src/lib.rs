@@ -0,0 +1,1218 @@
+use serde::{Deserialize, Serialize};
+
+pub mod batch;
+pub mod bench;
+pub mod compare;
+pub mod config;
+pub mod diff;
+pub mod float;
+pub mod merge;
+pub mod optimize;
+pub mod output;
+pub mod pareto;
+pub mod portable;
+pub mod presets;
+pub mod rng;
+pub mod run;
+pub mod sample;
+pub mod seeds;
+pub mod sim;
+pub mod sobol;
+pub mod stats;
+pub mod sweep;
+
+pub use float::Float;
+pub use optimize::Objective;
+pub use rng::{Lcg, Pcg64, Rng, RngKind};
+pub use run::{episode_seed, RunConfig};
+pub use sim::{SimConfig, SimConfigBuilder};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Params {
+    pub aggression: f64,
+    pub greed: f64,
+    pub safety: f64,
+    pub focus: f64,
+}
+
+/// Interval every parameter is clamped into; `[0, 1]` unless
+/// `--param-min`/`--param-max` widen it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParamBounds {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ParamBounds {
+    pub const UNIT: Self = Self { min: 0.0, max: 1.0 };
+
+    /// Checks `min < max`, both finite.
+    pub fn new(min: f64, max: f64) -> Result<Self, String> {
+        if !(min.is_finite() && max.is_finite() && min < max) {
+            return Err(format!(
+                "parameter bounds [{}, {}] need finite min < max",
+                min, max
+            ));
+        }
+        Ok(Self { min, max })
+    }
+
+    /// `v` limited to the bounds (NaN becomes the midpoint).
+    pub fn clamp(self, v: f64) -> f64 {
+        clamp(v, self.min, self.max)
+    }
+
+    /// Maps `u` in `[0, 1]` linearly onto the bounds.
+    pub fn scale(self, u: f64) -> f64 {
+        self.min + u * (self.max - self.min)
+    }
+}
+
+impl Default for ParamBounds {
+    fn default() -> Self {
+        Self::UNIT
+    }
+}
+
+impl Params {
+    /// Every parameter limited to `[0, 1]` (NaN becomes `0.5`).
+    pub fn clamped(self) -> Self {
+        self.clamped_to(ParamBounds::UNIT)
+    }
+
+    pub fn clamped_to(self, bounds: ParamBounds) -> Self {
+        self.map(|v| bounds.clamp(v))
+    }
+
+    /// `f` applied to each parameter.
+    pub fn map(self, f: impl Fn(f64) -> f64) -> Self {
+        Self {
+            aggression: f(self.aggression),
+            greed: f(self.greed),
+            safety: f(self.safety),
+            focus: f(self.focus),
+        }
+    }
+
+    /// Parses one `{aggression, greed, safety, focus}` JSON object.
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        if text.trim().is_empty() {
+            return Err("expected a JSON object, got empty input".to_string());
+        }
+        serde_json::from_str(text).map_err(|err| err.to_string())
+    }
+}
+
+/// Float type of the per-episode fields: `f64`, or `f32` with the `f32`
+/// feature, which halves `Episode` (32 to 16 bytes) for runs that keep
+/// every row. The model math (unless run through `Model::run_episode_as`)
+/// and all aggregates stay in `f64`; an `f32`
+/// `elapsed_s` only resolves about 1e-4 s, so its last printed decimals are
+/// noise.
+#[cfg(not(feature = "f32"))]
+pub type Real = f64;
+#[cfg(feature = "f32")]
+pub type Real = f32;
+
+/// An episode field as `f64`; a no-op without the `f32` feature.
+#[allow(clippy::useless_conversion)]
+pub fn widen(x: Real) -> f64 {
+    f64::from(x)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Episode {
+    pub unlock_rate: Real,
+    pub objective_complete: bool,
+    pub stability: Real,
+    pub elapsed_s: Real,
+    /// Why the objective failed; `None` when it completed.
+    pub fail_reason: Option<FailReason>,
+    /// `elapsed_s` is more than `outlier_k` standard deviations from the
+    /// run's mean. Only known once every row is in, so never set on
+    /// streamed episodes.
+    #[serde(default)]
+    pub outlier: bool,
+    /// Summary of a multi-step episode; absent for the one-shot model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trajectory: Option<Trajectory>,
+    /// Wall time spent computing this episode, in nanoseconds; only with
+    /// `RunConfig::time_episodes`, so it never changes other output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compute_ns: Option<u64>,
+}
+
+/// How a multi-step episode got to its final `unlock_rate` and `stability`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Trajectory {
+    pub steps: u32,
+    /// Unlock rate after the first step.
+    pub unlock_first: Real,
+    /// Lowest stability over the steps.
+    pub stability_min: Real,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailReason {
+    LowUnlock,
+    LowStability,
+    UnluckyDraw,
+}
+
+impl FailReason {
+    /// Blames the factor that, raised to `1.0` on its own, would have turned
+    /// `draw` into a success (the one with the bigger lift if both would);
+    /// `UnluckyDraw` when neither would have.
+    fn diagnose(coeffs: &Coefficients, unlock_rate: f64, stability: f64, draw: f64) -> Self {
+        let unlock_fixed = coeffs.objective_probability(1.0, stability);
+        let stability_fixed = coeffs.objective_probability(unlock_rate, 1.0);
+        if draw >= unlock_fixed.max(stability_fixed) {
+            Self::UnluckyDraw
+        } else if unlock_fixed >= stability_fixed {
+            Self::LowUnlock
+        } else {
+            Self::LowStability
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::LowUnlock => "low_unlock",
+            Self::LowStability => "low_stability",
+            Self::UnluckyDraw => "unlucky_draw",
+        }
+    }
+}
+
+/// Per-episode quantity that distribution summaries can be taken over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    UnlockRate,
+    Stability,
+    Elapsed,
+}
+
+impl Metric {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "unlock_rate" => Some(Self::UnlockRate),
+            "stability" => Some(Self::Stability),
+            "elapsed" => Some(Self::Elapsed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::UnlockRate => "unlock_rate",
+            Self::Stability => "stability",
+            Self::Elapsed => "elapsed",
+        }
+    }
+
+    pub fn of(self, ep: &Episode) -> f64 {
+        match self {
+            Self::UnlockRate => widen(ep.unlock_rate),
+            Self::Stability => widen(ep.stability),
+            Self::Elapsed => widen(ep.elapsed_s),
+        }
+    }
+}
+
+/// Unit elapsed times are reported in. The model always works in seconds;
+/// this only rescales the output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeUnit {
+    #[default]
+    #[serde(rename = "s")]
+    Seconds,
+    #[serde(rename = "m")]
+    Minutes,
+    #[serde(rename = "h")]
+    Hours,
+}
+
+impl TimeUnit {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "s" => Some(Self::Seconds),
+            "m" => Some(Self::Minutes),
+            "h" => Some(Self::Hours),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Seconds => "s",
+            Self::Minutes => "m",
+            Self::Hours => "h",
+        }
+    }
+
+    pub fn is_seconds(&self) -> bool {
+        *self == Self::Seconds
+    }
+
+    /// Seconds in one of this unit.
+    pub fn seconds(self) -> f64 {
+        match self {
+            Self::Seconds => 1.0,
+            Self::Minutes => 60.0,
+            Self::Hours => 3600.0,
+        }
+    }
+}
+
+impl Episode {
+    /// This episode with `elapsed_s` expressed in `unit`.
+    pub fn in_unit(mut self, unit: TimeUnit) -> Self {
+        self.elapsed_s = (widen(self.elapsed_s) / unit.seconds()) as Real;
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Aggregate {
+    pub episodes: usize,
+    pub objective_rate: f64,
+    /// Episodes that met the objective, the exact count behind
+    /// `objective_rate` for `merge`; `None` only when read from an output
+    /// written before the field existed.
+    #[serde(default)]
+    pub objective_hits: Option<usize>,
+    pub unlock_rate: f64,
+    pub stability_rate: f64,
+    pub mean_elapsed_s: f64,
+    pub unlock_std: f64,
+    pub stability_std: f64,
+    pub elapsed_std: f64,
+    pub unlock_var: f64,
+    pub stability_var: f64,
+    pub elapsed_var: f64,
+    /// `std / mean`; `None` (JSON `null`) when the mean is near zero.
+    pub unlock_cv: Option<f64>,
+    pub stability_cv: Option<f64>,
+    pub elapsed_cv: Option<f64>,
+    /// `None` (JSON `null`) when there were no episodes.
+    pub unlock_min: Option<f64>,
+    pub unlock_max: Option<f64>,
+    pub stability_min: Option<f64>,
+    pub stability_max: Option<f64>,
+    pub elapsed_min: Option<f64>,
+    pub elapsed_max: Option<f64>,
+    /// Pearson correlation of `unlock_rate` and `stability`; `None` when
+    /// either has zero variance (e.g. a single episode).
+    pub unlock_stability_corr: Option<f64>,
+    /// Moment skewness of `elapsed_s`; `null` when it has no spread.
+    pub elapsed_skewness: Option<f64>,
+    /// Excess kurtosis of `elapsed_s`; `null` when it has no spread.
+    pub elapsed_kurtosis: Option<f64>,
+    /// Wilson score interval for `objective_rate` at the run's `ci_level`.
+    pub objective_rate_wilson_lo: f64,
+    pub objective_rate_wilson_hi: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_p50: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_p90: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_p99: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unlock_median: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stability_median: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_median: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_iqr: Option<f64>,
+    /// Episodes flagged `outlier`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outliers: Option<usize>,
+    /// Mean `elapsed_s` without the top and bottom `trim` fraction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_trimmed_mean: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub objective_rate_lo: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub objective_rate_hi: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub histogram: Option<stats::Histogram>,
+    /// Covariance of `unlock_rate`, `stability` and `elapsed_s`; only with
+    /// `AggregateOptions::covariance`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub covariance: Option<stats::Covariance>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub antithetic: Option<VarianceReduction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inverse_sampling: Option<InverseSampling>,
+    /// Episodes run but left out of everything above by `min_stability`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filtered_out: Option<usize>,
+    /// The run was interrupted; `episodes` counts the ones that finished.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// Estimator variances of an antithetic run next to the naive ones (sample
+/// variance over `n`) that independent episodes would have given.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VarianceReduction {
+    /// Complete antithetic pairs the estimates come from.
+    pub pairs: usize,
+    pub objective_rate_var: f64,
+    pub objective_rate_naive_var: f64,
+    pub mean_elapsed_var: f64,
+    pub mean_elapsed_naive_var: f64,
+}
+
+/// Outcome of running until a fixed number of successes (`until_successes`).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InverseSampling {
+    pub target_successes: usize,
+    /// False when the episode cap was hit first; `rate` is then the plain
+    /// `successes / episodes` of a run that saw fewer than the target.
+    pub reached: bool,
+    pub successes: usize,
+    /// Episodes it took, the aggregate's `episodes`.
+    pub episodes: usize,
+    /// `(N - 1) / (n - 1)` for `N >= 2` successes in `n` episodes, the
+    /// unbiased estimator under inverse sampling; `N / n` otherwise.
+    pub rate: f64,
+}
+
+impl InverseSampling {
+    pub fn new(target_successes: usize, successes: usize, episodes: usize) -> Self {
+        let reached = successes >= target_successes;
+        let rate = if reached && successes >= 2 {
+            (successes - 1) as f64 / (episodes - 1) as f64
+        } else {
+            successes as f64 / episodes.max(1) as f64
+        };
+        Self {
+            target_successes,
+            reached,
+            successes,
+            episodes,
+            rate,
+        }
+    }
+}
+
+/// Knobs for the optional parts of the aggregate.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AggregateOptions {
+    /// Bootstrap resamples for the `objective_rate` interval; `0` disables it.
+    pub bootstrap: usize,
+    /// Metric to bin into `histogram`, if any.
+    pub histogram: Option<Metric>,
+    pub bins: usize,
+    /// Smallest prominence, as a fraction of the tallest bin, of a
+    /// histogram peak reported in its `modes`.
+    pub mode_prominence: f64,
+    /// Coverage of the Wilson interval, in `(0, 1)`.
+    pub ci_level: f64,
+    /// Fraction cut from each end for `elapsed_trimmed_mean`, in `[0, 0.5)`.
+    pub trim: f64,
+    /// Standard deviations from the mean `elapsed_s` beyond which a
+    /// retained episode is flagged `outlier`.
+    pub outlier_k: f64,
+    /// Add the `covariance` matrices of the three continuous metrics.
+    pub covariance: bool,
+    /// Fraction, in `[0, 0.5)`, of each tail every continuous metric is
+    /// clamped to before anything is summarized (`winsorized`); `0` is off.
+    pub winsorize: f64,
+}
+
+impl Default for AggregateOptions {
+    fn default() -> Self {
+        Self {
+            bootstrap: 0,
+            histogram: None,
+            bins: DEFAULT_HISTOGRAM_BINS,
+            mode_prominence: DEFAULT_MODE_PROMINENCE,
+            ci_level: DEFAULT_CI_LEVEL,
+            trim: 0.0,
+            outlier_k: DEFAULT_OUTLIER_K,
+            covariance: false,
+            winsorize: 0.0,
+        }
+    }
+}
+
+pub const DEFAULT_HISTOGRAM_BINS: usize = 20;
+pub const DEFAULT_MODE_PROMINENCE: f64 = 0.1;
+pub const DEFAULT_CI_LEVEL: f64 = 0.95;
+pub const DEFAULT_OUTLIER_K: f64 = 3.0;
+
+impl Aggregate {
+    /// This aggregate with every elapsed-time field expressed in `unit`:
+    /// means, spreads, extremes and quantiles scale by it, variances by its
+    /// square, and the unitless ratios (`elapsed_cv`, skewness, kurtosis)
+    /// stay as they are.
+    pub fn in_unit(&self, unit: TimeUnit) -> Self {
+        let k = unit.seconds().recip();
+        let linear = |v: Option<f64>| v.map(|v| v * k);
+        let mut scaled = self.clone();
+        scaled.mean_elapsed_s *= k;
+        scaled.elapsed_std *= k;
+        scaled.elapsed_var *= k * k;
+        scaled.elapsed_min = linear(self.elapsed_min);
+        scaled.elapsed_max = linear(self.elapsed_max);
+        scaled.elapsed_p50 = linear(self.elapsed_p50);
+        scaled.elapsed_p90 = linear(self.elapsed_p90);
+        scaled.elapsed_p99 = linear(self.elapsed_p99);
+        scaled.elapsed_median = linear(self.elapsed_median);
+        scaled.elapsed_iqr = linear(self.elapsed_iqr);
+        scaled.elapsed_trimmed_mean = linear(self.elapsed_trimmed_mean);
+        if let Some(histogram) = scaled.histogram.as_mut() {
+            if histogram.metric == Metric::Elapsed {
+                histogram.edges.iter_mut().for_each(|edge| *edge *= k);
+                histogram.modes.iter_mut().for_each(|mode| mode.center *= k);
+            }
+        }
+        if let Some(covariance) = scaled.covariance.as_mut() {
+            let elapsed = 2;
+            for i in 0..3 {
+                covariance.covariance[i][elapsed] *= k;
+                covariance.covariance[elapsed][i] *= k;
+            }
+        }
+        if let Some(antithetic) = scaled.antithetic.as_mut() {
+            antithetic.mean_elapsed_var *= k * k;
+            antithetic.mean_elapsed_naive_var *= k * k;
+        }
+        scaled
+    }
+
+    /// Fills in the statistics that need every row retained, replacing the
+    /// streaming percentile estimates with exact ones.
+    pub fn add_row_stats(&mut self, rows: &[Episode], trim: f64) {
+        if rows.is_empty() {
+            return;
+        }
+        // Order statistics by selection, not a full sort, in one reused copy.
+        // The trimmed mean goes first: with nothing trimmed it sums in row order.
+        let mut values: Vec<f64> = rows.iter().map(|ep| widen(ep.elapsed_s)).collect();
+        self.elapsed_trimmed_mean = Some(stats::select_trimmed_mean(&mut values, trim));
+        let mut at = |p: f64| stats::select_percentile(&mut values, p);
+        let p50 = at(0.50);
+        self.elapsed_p50 = Some(p50);
+        self.elapsed_p90 = Some(at(0.90));
+        self.elapsed_p99 = Some(at(0.99));
+        self.elapsed_median = Some(p50);
+        self.elapsed_iqr = Some(at(0.75) - at(0.25));
+        let mut median = |metric: fn(&Episode) -> Real| {
+            values.clear();
+            values.extend(rows.iter().map(|ep| widen(metric(ep))));
+            stats::select_percentile(&mut values, 0.5)
+        };
+        self.unlock_median = Some(median(|ep| ep.unlock_rate));
+        self.stability_median = Some(median(|ep| ep.stability));
+    }
+
+    /// Second pass over retained rows: flags episodes whose `elapsed_s` is
+    /// more than `k` standard deviations from the mean, and counts them.
+    pub fn tag_outliers(&mut self, rows: &mut [Episode], k: f64) {
+        let limit = k * self.elapsed_std;
+        let mut count = 0;
+        for ep in rows.iter_mut() {
+            ep.outlier = (widen(ep.elapsed_s) - self.mean_elapsed_s).abs() > limit;
+            count += usize::from(ep.outlier);
+        }
+        self.outliers = Some(count);
+    }
+
+    /// Bins `metric` over `rows` and marks the histogram's peaks of at
+    /// least `min_prominence`.
+    pub fn add_histogram(
+        &mut self,
+        rows: &[Episode],
+        metric: Metric,
+        bins: usize,
+        min_prominence: f64,
+    ) {
+        let values: Vec<f64> = rows.iter().map(|ep| metric.of(ep)).collect();
+        self.histogram = stats::histogram(metric, &values, bins).map(|mut histogram| {
+            histogram.modes = stats::modes(&histogram, min_prominence);
+            histogram
+        });
+    }
+
+    /// Bootstrap interval for `objective_rate`; needs every outcome retained.
+    pub fn add_bootstrap<R: Rng>(&mut self, outcomes: &[bool], resamples: usize, rng: &mut R) {
+        if outcomes.is_empty() || resamples == 0 {
+            return;
+        }
+        let (lo, hi) = stats::bootstrap_rate(outcomes, resamples, rng);
+        self.objective_rate_lo = Some(lo);
+        self.objective_rate_hi = Some(hi);
+    }
+}
+
+/// `rows` with `unlock_rate`, `stability` and `elapsed_s` each clamped to
+/// that metric's `fraction` and `1 - fraction` percentiles over `rows`, so
+/// extreme episodes pull the summaries less without any being dropped.
+/// `fraction` `0` (or no rows) returns them unchanged.
+pub fn winsorized(rows: &[Episode], fraction: f64) -> Vec<Episode> {
+    let mut out = rows.to_vec();
+    if rows.is_empty() || fraction <= 0.0 {
+        return out;
+    }
+    let mut values = Vec::with_capacity(rows.len());
+    let mut limits = |metric: Metric| {
+        values.clear();
+        values.extend(rows.iter().map(|ep| metric.of(ep)));
+        (
+            stats::select_percentile(&mut values, fraction),
+            stats::select_percentile(&mut values, 1.0 - fraction),
+        )
+    };
+    let (unlock, stability, elapsed) = (
+        limits(Metric::UnlockRate),
+        limits(Metric::Stability),
+        limits(Metric::Elapsed),
+    );
+    for ep in &mut out {
+        let pin = |v: Real, (lo, hi): (f64, f64)| widen(v).clamp(lo, hi) as Real;
+        ep.unlock_rate = pin(ep.unlock_rate, unlock);
+        ep.stability = pin(ep.stability, stability);
+        ep.elapsed_s = pin(ep.elapsed_s, elapsed);
+    }
+    out
+}
+
+/// What produced an output: enough to rerun it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Meta {
+    pub seed: u64,
+    #[serde(flatten)]
+    pub params: Params,
+    /// Requested episodes (the cap under `--target-ci`).
+    pub episodes: usize,
+    /// `sim-core` crate version.
+    pub version: String,
+    /// `RunConfig::run_id`, the same for every rerun of the same inputs.
+    #[serde(default)]
+    pub run_id: String,
+    /// When the run started, as ISO-8601 UTC; only with `--timestamp`, so
+    /// outputs stay reproducible by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    /// `AggregateOptions::winsorize` when the aggregate was winsorized.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winsorize: Option<f64>,
+    /// Unit of the elapsed fields when it isn't seconds.
+    #[serde(default, skip_serializing_if = "TimeUnit::is_seconds")]
+    pub time_unit: TimeUnit,
+}
+
+impl Meta {
+    pub fn new(config: &RunConfig) -> Self {
+        Self {
+            seed: config.seed,
+            params: config.params,
+            episodes: config.episodes,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            run_id: config.run_id(),
+            timestamp: None,
+            winsorize: Some(config.options.winsorize).filter(|&p| p > 0.0),
+            time_unit: TimeUnit::Seconds,
+        }
+    }
+}
+
+/// Top-level shape of a run's JSON output.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunOutput {
+    pub meta: Meta,
+    pub episodes: Vec<Episode>,
+    pub aggregate: Aggregate,
+}
+
+/// Limits `v` to `[lo, hi]`. NaN maps to the midpoint, so a bad input lands
+/// on a neutral value instead of spreading through the model.
+pub fn clamp<F: Float>(v: F, lo: F, hi: F) -> F {
+    debug_assert!(lo <= hi, "clamp bounds inverted: [{}, {}]", lo, hi);
+    if v.is_nan() {
+        lo + (hi - lo) / F::from_f64(2.0)
+    } else if v < lo {
+        lo
+    } else if v > hi {
+        hi
+    } else {
+        v
+    }
+}
+
+/// Shape of the additive perturbations in `run_episode`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Noise {
+    /// Uniform on each term's `[lo, hi]` range (the historical model).
+    #[default]
+    Uniform,
+    /// Normal centred on the range midpoint with standard deviation
+    /// `sigma * (hi - lo) / 2`; `DEFAULT_GAUSSIAN_SIGMA` matches the uniform variance.
+    Gaussian { sigma: f64 },
+}
+
+pub const DEFAULT_GAUSSIAN_SIGMA: f64 = 0.577_350_269_189_625_8;
+
+/// How `run_episode` decides `objective_complete`. The success draw is
+/// taken under every rule, so the rest of the episode stream is unchanged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SuccessRule {
+    /// `draw < objective_probability(unlock_rate, stability)` (the historical model).
+    #[default]
+    Bernoulli,
+    /// `unlock_rate >= unlock && stability >= stability`, no chance involved.
+    Threshold { unlock: f64, stability: f64 },
+    /// Both the Bernoulli draw and the thresholds must pass.
+    And { unlock: f64, stability: f64 },
+}
+
+pub const DEFAULT_SUCCESS_THRESHOLD: f64 = 0.5;
+
+/// Range every episode's `elapsed_s` is clamped to, whatever the model.
+pub const ELAPSED_MIN_S: f64 = 80.0;
+pub const ELAPSED_MAX_S: f64 = 2000.0;
+
+impl SuccessRule {
+    /// `objective_complete` and, on a miss, why.
+    fn decide(
+        self,
+        coeffs: &Coefficients,
+        unlock_rate: f64,
+        stability: f64,
+        draw: f64,
+    ) -> (bool, Option<FailReason>) {
+        let bernoulli = || {
+            let hit = draw < coeffs.objective_probability(unlock_rate, stability);
+            (
+                hit,
+                (!hit).then(|| FailReason::diagnose(coeffs, unlock_rate, stability, draw)),
+            )
+        };
+        let threshold = |unlock: f64, min_stability: f64| {
+            if unlock_rate < unlock {
+                Some(FailReason::LowUnlock)
+            } else if stability < min_stability {
+                Some(FailReason::LowStability)
+            } else {
+                None
+            }
+        };
+        match self {
+            Self::Bernoulli => bernoulli(),
+            Self::Threshold {
+                unlock,
+                stability: min_stability,
+            } => {
+                let reason = threshold(unlock, min_stability);
+                (reason.is_none(), reason)
+            }
+            Self::And {
+                unlock,
+                stability: min_stability,
+            } => match threshold(unlock, min_stability) {
+                Some(reason) => (false, Some(reason)),
+                None => bernoulli(),
+            },
+        }
+    }
+}
+
+/// Weights and thresholds of the episode model. The defaults are the
+/// historical constants; `*_penalty` terms only apply above their threshold.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Coefficients {
+    pub unlock_aggression: f64,
+    pub unlock_greed: f64,
+    pub unlock_focus: f64,
+    pub unlock_safety_penalty: f64,
+    pub unlock_safety_threshold: f64,
+    pub stability_safety: f64,
+    pub stability_focus: f64,
+    /// Weight of `|aggression - greed|`.
+    pub stability_imbalance: f64,
+    pub stability_aggression_penalty: f64,
+    pub stability_aggression_threshold: f64,
+    pub objective_base: f64,
+    pub objective_unlock: f64,
+    pub objective_stability: f64,
+    /// Seconds for an episode with zero unlock rate, before noise.
+    pub elapsed_base: f64,
+    /// Fraction of `elapsed_base` saved at full unlock rate.
+    pub elapsed_unlock: f64,
+    /// Share of the previous step's unlock rate and stability carried into
+    /// the next step of a multi-step episode.
+    pub step_decay: f64,
+    /// Stability lost per unit of unlock rate gained in one step.
+    pub step_strain: f64,
+    /// Half-width of the uniform noise added to each unlock draw.
+    pub unlock_noise: f64,
+    /// Half-width of the uniform noise added to each stability draw.
+    pub stability_noise: f64,
+    /// Half-width of the relative noise on `elapsed_s`, around
+    /// `ELAPSED_NOISE_CENTER`; the default gives the historical `[-0.08, 0.05]`.
+    pub elapsed_noise: f64,
+}
+
+/// Midpoint of the relative `elapsed_s` noise range: slightly negative, so
+/// noise shortens episodes a little on average.
+pub const ELAPSED_NOISE_CENTER: f64 = -0.015;
+
+impl Coefficients {
+    pub const DEFAULT: Self = Self {
+        unlock_aggression: 0.42,
+        unlock_greed: 0.36,
+        unlock_focus: 0.20,
+        unlock_safety_penalty: 0.10,
+        unlock_safety_threshold: 0.72,
+        stability_safety: 0.62,
+        stability_focus: 0.22,
+        stability_imbalance: 0.12,
+        stability_aggression_penalty: 0.08,
+        stability_aggression_threshold: 0.82,
+        objective_base: 0.18,
+        objective_unlock: 0.58,
+        objective_stability: 0.24,
+        elapsed_base: 1800.0,
+        elapsed_unlock: 0.65,
+        step_decay: 0.5,
+        step_strain: 0.5,
+        unlock_noise: 0.08,
+        stability_noise: 0.06,
+        elapsed_noise: 0.065,
+    };
+
+    fn objective_probability(&self, unlock_rate: f64, stability: f64) -> f64 {
+        clamp(
+            self.objective_base
+                + (self.objective_unlock * unlock_rate)
+                + (self.objective_stability * stability),
+            0.01,
+            0.99,
+        )
+    }
+}
+
+impl Default for Coefficients {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Model settings that change how an episode is simulated.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Model {
+    pub noise: Noise,
+    #[serde(default)]
+    pub coefficients: Coefficients,
+    /// Draw Gaussian noise with the `portable` `ln`/`cos` instead of the
+    /// platform's, so output is bit-identical across targets. The uniform
+    /// path needs no transcendentals and is portable either way.
+    #[serde(default)]
+    pub deterministic: bool,
+    #[serde(default)]
+    pub success: SuccessRule,
+    /// Steps per episode; `1` is the historical one-shot model.
+    #[serde(default = "single_step")]
+    pub steps: u32,
+}
+
+fn single_step() -> u32 {
+    1
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self {
+            noise: Noise::default(),
+            coefficients: Coefficients::DEFAULT,
+            deterministic: false,
+            success: SuccessRule::default(),
+            steps: 1,
+        }
+    }
+}
+
+impl Model {
+    fn perturb<R: Rng>(&self, rng: &mut R, lo: f64, hi: f64) -> f64 {
+        match self.noise {
+            Noise::Uniform => rng.range(lo, hi),
+            Noise::Gaussian { sigma } => {
+                let half = (hi - lo) / 2.0;
+                let z = if self.deterministic {
+                    portable::gaussian(rng)
+                } else {
+                    rng.next_gaussian()
+                };
+                lo + half + sigma * half * z
+            }
+        }
+    }
+
+    /// Lazy episodes at `params` from an `Lcg` seeded with `seed`.
+    pub fn episodes(self, params: Params, seed: u64) -> EpisodeIter {
+        EpisodeIter::new(self, params, seed)
+    }
+
+    /// Fills `out` in one tight loop; same episodes as calling `run_episode`
+    /// once per slot.
+    pub fn run_episodes_into<R: Rng>(&self, params: Params, rng: &mut R, out: &mut [Episode]) {
+        for slot in out {
+            *slot = self.run_episode(params, rng);
+        }
+    }
+
+    /// One step's unlock rate and stability from the parameters plus noise.
+    #[inline]
+    fn draw_state<F: Float, R: Rng>(&self, params: Params, rng: &mut R) -> (F, F) {
+        let c = &self.coefficients;
+        let k = F::from_f64;
+        let noise = k(self.perturb(rng, -c.unlock_noise, c.unlock_noise));
+        let unlock_rate = clamp(
+            k(c.unlock_aggression) * k(params.aggression)
+                + k(c.unlock_greed) * k(params.greed)
+                + k(c.unlock_focus) * k(params.focus)
+                - k(c.unlock_safety_penalty)
+                    * (k(params.safety) - k(c.unlock_safety_threshold)).max(k(0.0))
+                + noise,
+            k(0.0),
+            k(1.0),
+        );
+
+        let stability = clamp(
+            k(c.stability_safety) * k(params.safety) + k(c.stability_focus) * k(params.focus)
+                - k(c.stability_imbalance) * (k(params.aggression) - k(params.greed)).abs()
+                - k(c.stability_aggression_penalty)
+                    * (k(params.aggression) - k(c.stability_aggression_threshold)).max(k(0.0))
+                + k(self.perturb(rng, -c.stability_noise, c.stability_noise)),
+            k(0.0),
+            k(1.0),
+        );
+        (unlock_rate, stability)
+    }
+
+    /// Runs `steps` draws: unlock accumulates as `decay * unlock + fresh`,
+    /// stability relaxes towards each fresh draw and loses `step_strain` per
+    /// unit of unlock gained. The objective and elapsed time come from the
+    /// final state.
+    // `inline` here and on `draw_state`: without it the extra generic layer
+    // costs about 10% in `sim-core bench`.
+    #[inline]
+    pub fn run_episode<R: Rng>(&self, params: Params, rng: &mut R) -> Episode {
+        self.run_episode_as::<f64, R>(params, rng)
+    }
+
+    /// `run_episode` with the model arithmetic in `F`. The draws are the
+    /// same for every `F`; `f64` gives exactly `run_episode`, while `f32`
+    /// rounds every intermediate, so its fields drift in the last few
+    /// `f32` digits and a borderline objective draw can flip.
+    pub fn run_episode_as<F: Float, R: Rng>(&self, params: Params, rng: &mut R) -> Episode {
+        let c = &self.coefficients;
+        let k = F::from_f64;
+        let (mut unlock_rate, mut stability) = self.draw_state::<F, R>(params, rng);
+        let (unlock_first, mut stability_min) = (unlock_rate, stability);
+        for _ in 1..self.steps {
+            let (fresh_unlock, fresh_stability) = self.draw_state::<F, R>(params, rng);
+            let unlock = clamp(k(c.step_decay) * unlock_rate + fresh_unlock, k(0.0), k(1.0));
+            stability = clamp(
+                k(c.step_decay) * stability + (k(1.0) - k(c.step_decay)) * fresh_stability
+                    - k(c.step_strain) * (unlock - unlock_rate).max(k(0.0)),
+                k(0.0),
+                k(1.0),
+            );
+            unlock_rate = unlock;
+            stability_min = stability_min.min(stability);
+        }
+
+        let draw = rng.next_f64();
+        let (objective_complete, fail_reason) =
+            self.success
+                .decide(c, unlock_rate.to_f64(), stability.to_f64(), draw);
+
+        let mut elapsed_s = k(c.elapsed_base) * (k(1.0) - (k(c.elapsed_unlock) * unlock_rate));
+        let (lo, hi) = (
+            ELAPSED_NOISE_CENTER - c.elapsed_noise,
+            ELAPSED_NOISE_CENTER + c.elapsed_noise,
+        );
+        elapsed_s = elapsed_s * (k(1.0) + k(self.perturb(rng, lo, hi)));
+        elapsed_s = clamp(elapsed_s, k(ELAPSED_MIN_S), k(ELAPSED_MAX_S));
+
+        let real = |x: F| x.to_f64() as Real;
+        Episode {
+            unlock_rate: real(unlock_rate),
+            objective_complete,
+            stability: real(stability),
+            elapsed_s: real(elapsed_s),
+            fail_reason,
+            outlier: false,
+            trajectory: (self.steps > 1).then_some(Trajectory {
+                steps: self.steps,
+                unlock_first: real(unlock_first),
+                stability_min: real(stability_min),
+            }),
+            compute_ns: None,
+        }
+    }
+}
+
+pub fn run_episode<R: Rng>(params: Params, rng: &mut R) -> Episode {
+    Model::default().run_episode(params, rng)
+}
+
+/// Fills `out` with consecutive episodes from `rng`, as repeated
+/// `run_episode` calls would.
+pub fn run_episodes_into<R: Rng>(params: Params, rng: &mut R, out: &mut [Episode]) {
+    Model::default().run_episodes_into(params, rng, out);
+}
+
+/// Lazy, endless episode stream: each `next()` runs one episode from an
+/// owned `Lcg`, so callers can `take_while` or stop whenever they like. The
+/// episodes match a single-threaded `run` with the same seed.
+pub struct EpisodeIter {
+    model: Model,
+    params: Params,
+    rng: Lcg,
+}
+
+impl EpisodeIter {
+    pub fn new(model: Model, params: Params, seed: u64) -> Self {
+        Self {
+            model,
+            params,
+            rng: Lcg::new(seed),
+        }
+    }
+}
+
+impl Iterator for EpisodeIter {
+    type Item = Episode;
+
+    fn next(&mut self) -> Option<Episode> {
+        Some(self.model.run_episode(self.params, &mut self.rng))
+    }
+}
+
+/// `EpisodeIter` over the default model.
+pub fn episodes(params: Params, seed: u64) -> EpisodeIter {
+    EpisodeIter::new(Model::default(), params, seed)
+}
+
+/// Runs one episode from a fresh `Lcg`, e.g. with a seed from `episode_seed`.
+pub fn run_episode_seeded(params: Params, seed: u64) -> Episode {
+    run_episode(params, &mut Lcg::new(seed))
+}
+
+/// Single-pass (Welford) summaries over episodes in O(1) memory; `finish`
+/// turns them into an `Aggregate`.
+#[derive(Clone, Copy, Debug)]
+pub struct Accumulator {
+    episodes: usize,
+    objective_hits: usize,
+    unlock: stats::Running,
+    stability: stats::Running,
+    elapsed: stats::Running,
+    unlock_stability: stats::RunningCorr,
+    /// P² estimates of the elapsed p50/p90/p99.
+    elapsed_quantiles: [stats::P2Quantile; 3],
+}
+
+impl Default for Accumulator {
+    fn default() -> Self {
+        Self {
+            episodes: 0,
+            objective_hits: 0,
+            unlock: stats::Running::default(),
+            stability: stats::Running::default(),
+            elapsed: stats::Running::default(),
+            unlock_stability: stats::RunningCorr::default(),
+            elapsed_quantiles: [0.50, 0.90, 0.99].map(stats::P2Quantile::new),
+        }
+    }
+}
+
+impl Accumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, ep: &Episode) {
+        self.episodes += 1;
+        self.objective_hits += usize::from(ep.objective_complete);
+        let (unlock, stability, elapsed) = (
+            widen(ep.unlock_rate),
+            widen(ep.stability),
+            widen(ep.elapsed_s),
+        );
+        self.unlock.push(unlock);
+        self.stability.push(stability);
+        self.elapsed.push(elapsed);
+        self.unlock_stability.push(unlock, stability);
+        for q in &mut self.elapsed_quantiles {
+            q.push(elapsed);
+        }
+    }
+
+    /// The streaming state behind a saved aggregate, for `merge`. Needs the
+    /// exact `objective_hits`, since `objective_rate` rounded to a few
+    /// decimals no longer pins the count down; the quantile estimators start
+    /// empty, since P² markers can't be merged.
+    pub fn from_aggregate(aggregate: &Aggregate) -> Result<Self, String> {
+        let count = aggregate.episodes as u64;
+        let hits = aggregate
+            .objective_hits
+            .ok_or("the aggregate has no objective_hits count")?;
+        Ok(Self {
+            episodes: aggregate.episodes,
+            objective_hits: hits,
+            unlock: stats::Running::from_moments(
+                count,
+                aggregate.unlock_rate,
+                aggregate.unlock_var,
+                None,
+                None,
+                aggregate.unlock_min,
+                aggregate.unlock_max,
+            ),
+            stability: stats::Running::from_moments(
+                count,
+                aggregate.stability_rate,
+                aggregate.stability_var,
+                None,
+                None,
+                aggregate.stability_min,
+                aggregate.stability_max,
+            ),
+            elapsed: stats::Running::from_moments(
+                count,
+                aggregate.mean_elapsed_s,
+                aggregate.elapsed_var,
+                aggregate.elapsed_skewness,
+                aggregate.elapsed_kurtosis,
+                aggregate.elapsed_min,
+                aggregate.elapsed_max,
+            ),
+            unlock_stability: stats::RunningCorr::from_moments(
+                count,
+                (aggregate.unlock_rate, aggregate.unlock_var),
+                (aggregate.stability_rate, aggregate.stability_var),
+                aggregate.unlock_stability_corr,
+            ),
+            ..Self::default()
+        })
+    }
+
+    /// Folds in `other` as if its episodes had been pushed here; the
+    /// quantile estimates keep only this side's episodes.
+    pub fn merge(&mut self, other: &Self) {
+        self.episodes += other.episodes;
+        self.objective_hits += other.objective_hits;
+        self.unlock.merge(&other.unlock);
+        self.stability.merge(&other.stability);
+        self.elapsed.merge(&other.elapsed);
+        self.unlock_stability.merge(&other.unlock_stability);
+    }
+
+    /// `finish_with_ci` at the default 95% level.
+    pub fn finish(&self) -> Aggregate {
+        self.finish_with_ci(DEFAULT_CI_LEVEL)
+    }
+
+    pub fn finish_with_ci(&self, ci_level: f64) -> Aggregate {
+        let n = self.episodes as f64;
+        let (wilson_lo, wilson_hi) = stats::wilson_interval(
+            self.objective_hits,
+            self.episodes,
+            stats::two_sided_z(ci_level),
+        );
+        Aggregate {
+            episodes: self.episodes,
+            objective_rate: self.objective_hits as f64 / n,
+            objective_hits: Some(self.objective_hits),
+            unlock_rate: self.unlock.mean(),
+            stability_rate: self.stability.mean(),
+            mean_elapsed_s: self.elapsed.mean(),
+            unlock_std: self.unlock.std(),
+            stability_std: self.stability.std(),
+            elapsed_std: self.elapsed.std(),
+            unlock_var: self.unlock.variance(),
+            stability_var: self.stability.variance(),
+            elapsed_var: self.elapsed.variance(),
+            unlock_cv: self.unlock.cv(),
+            stability_cv: self.stability.cv(),
+            elapsed_cv: self.elapsed.cv(),
+            unlock_min: self.unlock.min(),
+            unlock_max: self.unlock.max(),
+            stability_min: self.stability.min(),
+            stability_max: self.stability.max(),
+            elapsed_min: self.elapsed.min(),
+            elapsed_max: self.elapsed.max(),
+            unlock_stability_corr: self.unlock_stability.correlation(),
+            elapsed_skewness: self.elapsed.skewness(),
+            elapsed_kurtosis: self.elapsed.excess_kurtosis(),
+            objective_rate_wilson_lo: wilson_lo,
+            objective_rate_wilson_hi: wilson_hi,
+            elapsed_p50: self.elapsed_quantiles[0].estimate(),
+            elapsed_p90: self.elapsed_quantiles[1].estimate(),
+            elapsed_p99: self.elapsed_quantiles[2].estimate(),
+            unlock_median: None,
+            stability_median: None,
+            elapsed_median: None,
+            elapsed_iqr: None,
+            elapsed_trimmed_mean: None,
+            outliers: None,
+            objective_rate_lo: None,
+            objective_rate_hi: None,
+            histogram: None,
+            covariance: None,
+            antithetic: None,
+            inverse_sampling: None,
+            filtered_out: None,
+            truncated: false,
+        }
+    }
+}
+
+/// Rows plus aggregate of one run, without the `meta` of a CLI output.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SimResult {
+    pub episodes: Vec<Episode>,
+    pub aggregate: Aggregate,
+}
+
+/// Entry point for embedders such as a wasm-bindgen wrapper: the default
+/// model on one thread, no arguments, environment, clock or output touched.
+/// The CLI's `run` gives the same rows and aggregate for the same values.
+pub fn simulate(params: Params, seed: u64, episodes: usize) -> SimResult {
+    let (episodes, aggregate) = RunConfig::new(params, seed, episodes).run();
+    SimResult {
+        episodes,
+        aggregate,
+    }
+}
+
+/// `simulate` reduced to one number: the run's aggregate scored by
+/// `objective`, as the optimizers score their candidates. Keeps no rows.
+pub fn score(params: Params, seed: u64, episodes: usize, objective: Objective) -> f64 {
+    score_config(&RunConfig::new(params, seed, episodes), objective).0
+}
+
+/// `score` for a full `config`, with the streamed aggregate it came from;
+/// what every optimizer candidate goes through.
+pub fn score_config(config: &RunConfig, objective: Objective) -> (f64, Aggregate) {
+    let aggregate = config.stream(|_| {});
+    (objective.score(&aggregate), aggregate)
+}
+
+/// Runs `episodes` episodes from a single `Lcg` stream and summarizes them.
+pub fn run_batch(params: Params, seed: u64, episodes: usize) -> (Vec<Episode>, Aggregate) {
+    RunConfig::new(params, seed, episodes).run()
+}
@@ -0,0 +1,102 @@
+//! How much diagnostic output goes to stderr, and in what form. Results on
+//! stdout, the `csv` aggregate, `--chart`, `bench` reports and errors are not
+//! diagnostics and print at every level.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde_json::{Map, Value};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// `--quiet`: nothing but errors.
+    Quiet,
+    /// Warnings, `--progress` and summary lines.
+    Normal,
+    /// `--verbose`: also the resolved parameters, seed and wall time.
+    Verbose,
+}
+
+impl LogLevel {
+    pub fn warnings(self) -> bool {
+        self >= Self::Normal
+    }
+
+    pub fn verbose(self) -> bool {
+        self == Self::Verbose
+    }
+}
+
+/// How diagnostic lines are written (`--log-format`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `tag: message`, one line each.
+    #[default]
+    Text,
+    /// One JSON object per line with `level`, `msg` and the line's fields.
+    Json,
+}
+
+impl LogFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Self::Text),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Set once from `--log-format` before anything is logged.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+pub fn set_format(format: LogFormat) {
+    JSON.store(format == LogFormat::Json, Ordering::Relaxed);
+}
+
+/// The `level` of a diagnostic under `--log-format json`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+    /// `--verbose` lines.
+    Debug,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+            Self::Debug => "debug",
+        }
+    }
+}
+
+/// Writes one diagnostic line to stderr: `tag: msg` as text, or under
+/// `--log-format json` an object of `level`, `target` (the tag, left out
+/// when it just repeats the level), `msg` and then the entries of
+/// `fields`, which is a JSON object or `Value::Null` for none. Callers
+/// check the `LogLevel` first; this only formats.
+pub fn emit(severity: Severity, tag: &str, msg: &str, fields: Value) {
+    if !JSON.load(Ordering::Relaxed) {
+        eprintln!("{}: {}", tag, msg);
+        return;
+    }
+    let mut line = Map::new();
+    line.insert("level".to_string(), severity.as_str().into());
+    if tag != severity.as_str() {
+        line.insert("target".to_string(), tag.into());
+    }
+    line.insert("msg".to_string(), msg.into());
+    if let Value::Object(fields) = fields {
+        line.extend(fields);
+    }
+    eprintln!("{}", Value::Object(line));
+}
+
+/// A `warning: msg` line.
+pub fn warning(msg: &str, fields: Value) {
+    emit(Severity::Warning, "warning", msg, fields);
+}
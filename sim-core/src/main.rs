@@ -1,187 +1,909 @@
 use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-#[derive(Clone, Copy)]
-struct Params {
-    aggression: f64,
-    greed: f64,
-    safety: f64,
-    focus: f64,
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sim_core::batch;
+use sim_core::bench::bench;
+use sim_core::compare::compare;
+use sim_core::diff::diff;
+use sim_core::merge::merge;
+use sim_core::optimize::{anneal, gradient, hill_climb, nelder_mead, random_search};
+use sim_core::output::{
+    chart, load_aggregate, table, utc_timestamp, Format, Style, CHART_WIDTH, CSV_HEADER,
+};
+use sim_core::pareto::pareto;
+use sim_core::sample::Sampler;
+use sim_core::seeds::{self, run_seed_list, run_seeds};
+use sim_core::sobol::sobol;
+use sim_core::stats::histogram;
+use sim_core::sweep::{Grid, ParamRange, SweepRow, LARGE_GRID_CELLS};
+use sim_core::{
+    Accumulator, Aggregate, Episode, Meta, Metric, RngKind, RunConfig, RunOutput, TimeUnit,
+};
+
+mod cli;
+mod interrupt;
+mod log;
+
+use cli::{
+    BenchArgs, Cli, Command, CompareArgs, DiffArgs, GradientArgs, MergeArgs, OptimizeArgs,
+    ParetoArgs, RunArgs, SobolArgs, SweepArgs,
+};
+use log::Severity;
+
+/// Runs smaller than this stay quiet even with `--progress`.
+const PROGRESS_MIN_EPISODES: usize = 100_000;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+/// Episodes between clock reads, so timing stays off the hot path.
+const PROGRESS_CHECK_EVERY: usize = 1024;
+
+/// `--progress` reporter: a start line, a percentage and rate on stderr
+/// about every `PROGRESS_INTERVAL`, and the total wall time at the end.
+struct Progress {
+    enabled: bool,
+    total: usize,
+    done: usize,
+    started: Instant,
+    last: Instant,
 }
 
-#[derive(Clone, Copy)]
-struct Episode {
-    unlock_rate: f64,
-    objective_complete: bool,
-    stability: f64,
-    elapsed_s: f64,
+impl Progress {
+    fn new(enabled: bool, config: &RunConfig) -> Self {
+        let enabled = enabled && config.episodes >= PROGRESS_MIN_EPISODES;
+        if enabled {
+            let msg = format!("seed {}, {} episodes", config.seed, config.episodes);
+            let fields = json!({"seed": config.seed, "total": config.episodes});
+            log::emit(Severity::Info, "progress", &msg, fields);
+        }
+        let now = Instant::now();
+        Self {
+            enabled,
+            total: config.episodes,
+            done: 0,
+            started: now,
+            last: now,
+        }
+    }
+
+    fn tick(&mut self) {
+        self.done += 1;
+        if !self.enabled || !self.done.is_multiple_of(PROGRESS_CHECK_EVERY) {
+            return;
+        }
+        let now = Instant::now();
+        if now.duration_since(self.last) < PROGRESS_INTERVAL {
+            return;
+        }
+        self.last = now;
+        let secs = now.duration_since(self.started).as_secs_f64();
+        let msg = format!(
+            "{:5.1}% ({}/{}) {:.0} episodes/s",
+            100.0 * self.done as f64 / self.total as f64,
+            self.done,
+            self.total,
+            self.done as f64 / secs
+        );
+        let fields = json!({
+            "episodes": self.done,
+            "total": self.total,
+            "elapsed_s": secs,
+            "episodes_per_s": self.done as f64 / secs,
+        });
+        log::emit(Severity::Info, "progress", &msg, fields);
+    }
+
+    fn finish(&self) {
+        if self.enabled {
+            let secs = self.started.elapsed().as_secs_f64();
+            let msg = format!("done, {} episodes in {:.2}s", self.done, secs);
+            let fields = json!({"episodes": self.done, "elapsed_s": secs});
+            log::emit(Severity::Info, "progress", &msg, fields);
+        }
+    }
 }
 
-struct Lcg {
-    state: u64,
+/// `--chart` collector: keeps the chosen metric of every episode, then
+/// prints its text histogram to stderr.
+struct Chart {
+    metric: Option<Metric>,
+    bins: usize,
+    unit: TimeUnit,
+    values: Vec<f64>,
 }
 
-impl Lcg {
-    fn new(seed: u64) -> Self {
-        let initial = if seed == 0 { 0x9e3779b97f4a7c15 } else { seed };
-        Self { state: initial }
+impl Chart {
+    fn new(metric: Option<&str>, bins: usize, unit: TimeUnit) -> Self {
+        Self {
+            metric: metric.and_then(Metric::parse),
+            bins,
+            unit,
+            values: Vec::new(),
+        }
     }
 
-    fn next_u64(&mut self) -> u64 {
-        self.state = self
-            .state
-            .wrapping_mul(6364136223846793005)
-            .wrapping_add(1442695040888963407);
-        self.state
+    fn push(&mut self, ep: &Episode) {
+        if let Some(metric) = self.metric {
+            self.values.push(metric.of(&ep.in_unit(self.unit)));
+        }
     }
 
-    fn next_f64(&mut self) -> f64 {
-        let x = self.next_u64() >> 11;
-        (x as f64) / ((1u64 << 53) as f64)
+    fn print(&self) {
+        let histogram = self
+            .metric
+            .and_then(|metric| histogram(metric, &self.values, self.bins));
+        if let Some(histogram) = histogram {
+            eprint!("{}", chart(&histogram, CHART_WIDTH));
+        }
     }
+}
 
-    fn range(&mut self, lo: f64, hi: f64) -> f64 {
-        lo + (hi - lo) * self.next_f64()
+/// Leading `ndjson` line; wraps `Meta` so it can't be mistaken for an episode.
+#[derive(Serialize)]
+struct MetaLine {
+    meta: Meta,
+}
+
+fn stream_ndjson(
+    config: &RunConfig,
+    meta: Meta,
+    out: &mut dyn Write,
+    progress: &mut Progress,
+    chart: &mut Chart,
+    style: Style,
+    unit: TimeUnit,
+) -> io::Result<Aggregate> {
+    writeln!(out, "{}", style.line(&MetaLine { meta })?)?;
+    let mut result = Ok(());
+    let aggregate = config.stream(|ep| {
+        progress.tick();
+        chart.push(ep);
+        if result.is_ok() {
+            result = style
+                .line(&ep.in_unit(unit))
+                .map_err(io::Error::from)
+                .and_then(|line| writeln!(out, "{}", line));
+        }
+    });
+    result?;
+    let aggregate = aggregate.in_unit(unit);
+    writeln!(out, "{}", style.line(&aggregate)?)?;
+    Ok(aggregate)
+}
+
+/// One `--emit-every` line: the aggregate so far and how many episodes it covers.
+#[derive(Serialize)]
+struct Snapshot<'a> {
+    cumulative_episodes: usize,
+    #[serde(flatten)]
+    aggregate: &'a Aggregate,
+}
+
+/// Streams the run, writing a rolling aggregate from a second `Accumulator`
+/// every `every` kept episodes and the run's full aggregate at the end. A
+/// snapshot at the last planned episode is left to the final line.
+fn stream_snapshots(
+    config: &RunConfig,
+    every: usize,
+    out: &mut dyn Write,
+    progress: &mut Progress,
+    chart: &mut Chart,
+    style: Style,
+    unit: TimeUnit,
+) -> io::Result<Aggregate> {
+    let mut rolling = Accumulator::new();
+    let mut count = 0usize;
+    let mut result = Ok(());
+    let aggregate = config.stream(|ep| {
+        progress.tick();
+        chart.push(ep);
+        rolling.push(ep);
+        count += 1;
+        if result.is_ok() && count.is_multiple_of(every) && count < config.episodes {
+            let snapshot = rolling
+                .finish_with_ci(config.options.ci_level)
+                .in_unit(unit);
+            result = style
+                .line(&Snapshot {
+                    cumulative_episodes: count,
+                    aggregate: &snapshot,
+                })
+                .map_err(io::Error::from)
+                .and_then(|line| writeln!(out, "{}", line));
+        }
+    });
+    result?;
+    let aggregate = aggregate.in_unit(unit);
+    let last = Snapshot {
+        cumulative_episodes: aggregate.episodes,
+        aggregate: &aggregate,
+    };
+    writeln!(out, "{}", style.line(&last)?)?;
+    Ok(aggregate)
+}
+
+fn run_sweep(
+    args: &SweepArgs,
+    base: &RunConfig,
+    grid: &Grid,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    if grid.cells() > LARGE_GRID_CELLS && args.common.log.level().warnings() {
+        let msg = format!(
+            "sweep has {} grid cells x {} episodes",
+            grid.cells(),
+            base.episodes
+        );
+        log::warning(
+            &msg,
+            json!({"cells": grid.cells(), "episodes": base.episodes}),
+        );
     }
+
+    for params in grid.iter() {
+        let (_, aggregate) = RunConfig { params, ..*base }.run();
+        let row = SweepRow { params, aggregate };
+        writeln!(out, "{}", args.common.style().line(&row)?)?;
+    }
+    Ok(())
 }
 
-fn clamp(v: f64, lo: f64, hi: f64) -> f64 {
-    if v < lo {
-        lo
-    } else if v > hi {
-        hi
-    } else {
-        v
+/// Why a command did not succeed; each kind has its own exit status.
+#[derive(Debug)]
+enum Failure {
+    /// Bad input or I/O (exit 1).
+    Error(String),
+    /// `--fail-under` was not met (exit 2).
+    BelowThreshold { objective_rate: f64, threshold: f64 },
+    /// Ctrl-C; the partial output has been written (exit 130).
+    Interrupted,
+}
+
+impl Failure {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::Error(_) => ExitCode::FAILURE,
+            Self::BelowThreshold { .. } => ExitCode::from(2),
+            Self::Interrupted => ExitCode::from(interrupt::EXIT_INTERRUPTED),
+        }
+    }
+
+    /// Prefix of the stderr line.
+    fn tag(&self) -> &'static str {
+        match self {
+            Self::Error(_) => "error",
+            Self::BelowThreshold { .. } => "fail",
+            Self::Interrupted => "interrupted",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Self::Error(msg) => msg.clone(),
+            Self::BelowThreshold {
+                objective_rate,
+                threshold,
+            } => format!(
+                "objective_rate {} is below --fail-under {}",
+                objective_rate, threshold
+            ),
+            Self::Interrupted => "output covers only the episodes that finished".to_string(),
+        }
+    }
+}
+
+impl From<String> for Failure {
+    fn from(msg: String) -> Self {
+        Self::Error(msg)
     }
 }
 
-fn parse_flag(args: &[String], key: &str, default: f64) -> f64 {
-    let mut i = 0usize;
-    while i + 1 < args.len() {
-        if args[i] == key {
-            if let Ok(v) = args[i + 1].parse::<f64>() {
-                return v;
+/// Result sink: the `--output` file (created or truncated) or stdout,
+/// gzip-compressed as it is written for `--gzip` or a `.gz` path.
+enum Output {
+    Plain(Box<dyn Write>),
+    Gzip(GzEncoder<Box<dyn Write>>),
+}
+
+impl Output {
+    /// Flushes, writing the gzip trailer first so the stream is complete.
+    fn finish(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(out) => out.flush(),
+            Self::Gzip(out) => {
+                out.try_finish()?;
+                out.get_mut().flush()
             }
-            return default;
         }
-        i += 1;
     }
-    default
 }
 
-fn parse_flag_u64(args: &[String], key: &str, default: u64) -> u64 {
-    let mut i = 0usize;
-    while i + 1 < args.len() {
-        if args[i] == key {
-            if let Ok(v) = args[i + 1].parse::<u64>() {
-                return v;
-            }
-            return default;
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(out) => out.write(buf),
+            Self::Gzip(out) => out.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(out) => out.flush(),
+            Self::Gzip(out) => out.flush(),
         }
-        i += 1;
     }
-    default
 }
 
-fn parse_flag_usize(args: &[String], key: &str, default: usize) -> usize {
-    let mut i = 0usize;
-    while i + 1 < args.len() {
-        if args[i] == key {
-            if let Ok(v) = args[i + 1].parse::<usize>() {
-                return v.max(1);
-            }
-            return default;
+fn open_output(path: Option<&Path>, gzip: bool) -> Result<Output, Failure> {
+    let out: Box<dyn Write> = match path {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(err) => return Err(format!("cannot open output {:?}: {}", path, err).into()),
+        },
+        None => Box::new(io::stdout().lock()),
+    };
+    let gzip = gzip || path.is_some_and(|path| path.extension().is_some_and(|ext| ext == "gz"));
+    Ok(if gzip {
+        Output::Gzip(GzEncoder::new(out, Compression::default()))
+    } else {
+        Output::Plain(out)
+    })
+}
+
+fn finish_output(result: io::Result<()>, out: &mut Output) -> Result<(), Failure> {
+    if let Err(err) = result.and_then(|()| out.finish()) {
+        return Err(format!("cannot write output: {}", err).into());
+    }
+    if interrupt::interrupted() {
+        return Err(Failure::Interrupted);
+    }
+    Ok(())
+}
+
+fn check_threshold(aggregate: &Aggregate, threshold: Option<f64>) -> Result<(), Failure> {
+    match threshold {
+        Some(threshold) if aggregate.objective_rate < threshold => Err(Failure::BelowThreshold {
+            objective_rate: aggregate.objective_rate,
+            threshold,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Whether `--color WHEN` lets stderr output use ANSI colour.
+fn use_color(when: &str) -> bool {
+    match when {
+        "always" => true,
+        "never" => false,
+        _ => io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none(),
+    }
+}
+
+/// End-of-run work shared by every `run` output mode: writes
+/// `--aggregate-out`, prints `--table`, warns when `--until-successes` hit
+/// the episode cap, then applies `--fail-under`.
+fn check_run(aggregate: &Aggregate, args: &RunArgs) -> Result<(), Failure> {
+    if let Some(path) = &args.aggregate_out {
+        let mut file = open_output(Some(path), args.common.gzip)?;
+        let written = write!(
+            file,
+            "{}",
+            args.common
+                .style()
+                .render(aggregate)
+                .expect("aggregate serializes")
+        );
+        finish_output(written, &mut file)?;
+    }
+    if args.table {
+        eprint!(
+            "{}",
+            table(aggregate, args.fail_under, use_color(&args.color))
+        );
+    }
+    if let Some(inverse) = aggregate.inverse_sampling {
+        if !inverse.reached && !aggregate.truncated && args.common.log.level().warnings() {
+            let msg = format!(
+                "only {} of --until-successes {} within {} episodes; the rate is a plain ratio, \
+                 raise --max-episodes for the target",
+                inverse.successes, inverse.target_successes, inverse.episodes
+            );
+            let fields = json!({
+                "successes": inverse.successes,
+                "target_successes": inverse.target_successes,
+                "episodes": inverse.episodes,
+            });
+            log::warning(&msg, fields);
         }
-        i += 1;
     }
-    default
+    check_threshold(aggregate, args.fail_under)
 }
 
-fn run_episode(params: Params, rng: &mut Lcg) -> Episode {
-    let noise = rng.range(-0.08, 0.08);
-    let unlock_rate = clamp(
-        0.42 * params.aggression
-            + 0.36 * params.greed
-            + 0.20 * params.focus
-            - 0.10 * (params.safety - 0.72).max(0.0)
-            + noise,
-        0.0,
-        1.0,
-    );
+/// The `{meta, episodes, aggregate}` object, without a trailing newline.
+fn write_json(
+    out: &mut dyn Write,
+    meta: Meta,
+    rows: Vec<Episode>,
+    aggregate: &Aggregate,
+    style: Style,
+) -> io::Result<()> {
+    let output = RunOutput {
+        meta,
+        episodes: rows,
+        aggregate: aggregate.clone(),
+    };
+    write!(
+        out,
+        "{}",
+        style.render(&output).expect("run output serializes")
+    )
+}
 
-    let stability = clamp(
-        0.62 * params.safety
-            + 0.22 * params.focus
-            - 0.12 * (params.aggression - params.greed).abs()
-            - 0.08 * (params.aggression - 0.82).max(0.0)
-            + rng.range(-0.06, 0.06),
-        0.0,
-        1.0,
-    );
+/// The CSV rows under `CSV_HEADER`, whose `elapsed_s` column is renamed
+/// for another `unit`, with a `compute_ns` column for timed episodes.
+fn write_csv(
+    out: &mut dyn Write,
+    rows: &[Episode],
+    style: Style,
+    unit: TimeUnit,
+) -> io::Result<()> {
+    let elapsed = format!("elapsed_{}", unit.as_str());
+    let mut header = CSV_HEADER.replace("elapsed_s", &elapsed);
+    if rows.iter().any(|ep| ep.compute_ns.is_some()) {
+        header += ",compute_ns";
+    }
+    writeln!(out, "{}", header)?;
+    rows.iter()
+        .try_for_each(|ep| writeln!(out, "{}", style.csv_row(ep)))
+}
+
+/// Final `Lcg` state of a run, for `--dump-rng`.
+static RNG_STATE: AtomicU64 = AtomicU64::new(0);
+
+fn read_rng_state(path: &Path) -> Result<u64, Failure> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("cannot read rng state {}: {}", path.display(), err))?;
+    text.trim()
+        .parse()
+        .map_err(|err| format!("invalid rng state {}: {}", path.display(), err).into())
+}
+
+fn run(args: RunArgs) -> Result<(), Failure> {
+    let mut config = args.common.config()?;
+    interrupt::install();
+    config.stop = Some(&interrupt::INTERRUPTED);
+    if args.dump_rng.is_some() || args.resume_rng.is_some() {
+        if config.rng != RngKind::Lcg
+            || config.threads > 1
+            || config.independent_seeds
+            || config.target_ci.is_some()
+            || config.time_budget.is_some()
+            || config.until_successes.is_some()
+        {
+            let msg = "--dump-rng and --resume-rng need --rng lcg, one thread, a shared stream \
+                       and a fixed --episodes count";
+            return Err(msg.to_string().into());
+        }
+        if let Some(path) = &args.resume_rng {
+            config.rng_state = Some(read_rng_state(path)?);
+        }
+        config.checkpoint = Some(&RNG_STATE);
+    }
+    let result = run_episodes(&args, &config);
+    // A failed or interrupted run stopped part-way through its last chunk.
+    if let (Some(path), Ok(()) | Err(Failure::BelowThreshold { .. })) = (&args.dump_rng, &result) {
+        let state = RNG_STATE.load(Ordering::Relaxed);
+        fs::write(path, format!("{}\n", state))
+            .map_err(|err| format!("cannot write rng state {}: {}", path.display(), err))?;
+    }
+    result
+}
+
+/// The current time for `--timestamp`.
+fn now_utc() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    utc_timestamp(secs)
+}
+
+/// A kept run's rows and aggregate rescaled for `--time-unit`.
+fn in_unit(rows: Vec<Episode>, aggregate: &Aggregate, unit: TimeUnit) -> (Vec<Episode>, Aggregate) {
+    let rows = rows.into_iter().map(|ep| ep.in_unit(unit)).collect();
+    (rows, aggregate.in_unit(unit))
+}
+
+fn run_episodes(args: &RunArgs, config: &RunConfig) -> Result<(), Failure> {
+    let style = args.common.style();
+    let unit = TimeUnit::parse(&args.time_unit).expect("clap restricts --time-unit");
+    let meta = Meta {
+        timestamp: args.timestamp.then(now_utc),
+        time_unit: unit,
+        ..Meta::new(config)
+    };
+    let log = args.common.log.level();
 
-    let objective_p = clamp(0.18 + (0.58 * unlock_rate) + (0.24 * stability), 0.01, 0.99);
-    let objective_complete = rng.next_f64() < objective_p;
+    // Before stdout is opened, so --gzip leaves it empty.
+    if args.json_out.is_some() || args.csv_out.is_some() {
+        let mut progress = Progress::new(args.progress && log.warnings(), config);
+        let mut chart = Chart::new(args.chart.as_deref(), args.common.bins, unit);
+        let (rows, aggregate) = config.run_with(|ep| {
+            progress.tick();
+            chart.push(ep);
+        });
+        progress.finish();
+        chart.print();
+        let (rows, aggregate) = in_unit(rows, &aggregate, unit);
+        // CSV first: the JSON output takes ownership of the rows.
+        if let Some(path) = &args.csv_out {
+            let mut file = open_output(Some(path), args.common.gzip)?;
+            let written = write_csv(&mut file, &rows, style, unit);
+            finish_output(written, &mut file)?;
+        }
+        if let Some(path) = &args.json_out {
+            let mut file = open_output(Some(path), args.common.gzip)?;
+            let written = write_json(&mut file, meta, rows, &aggregate, style);
+            finish_output(written, &mut file)?;
+        }
+        return check_run(&aggregate, args);
+    }
+
+    let mut out = open_output(args.common.output.as_deref(), args.common.gzip)?;
+
+    if let Some(index) = args.replay {
+        let episode = config.replay(index).ok_or_else(|| {
+            format!(
+                "--replay {} is past the last of {} episodes",
+                index, config.episodes
+            )
+        })?;
+        let written = writeln!(
+            out,
+            "{}",
+            style
+                .render(&episode.in_unit(unit))
+                .expect("episode serializes")
+        );
+        return finish_output(written, &mut out);
+    }
+
+    if let Some(path) = &args.params_file {
+        let sets = batch::load(path)?;
+        let rows = batch::run_sets(config, &sets);
+        let written = writeln!(
+            out,
+            "{}",
+            style.render(&rows).expect("batch rows serialize")
+        );
+        return finish_output(written, &mut out);
+    }
+
+    let sweep = match (args.seeds, &args.seeds_file) {
+        (Some(count), _) => Some(run_seeds(config, count)),
+        (None, Some(path)) => Some(run_seed_list(config, &seeds::load(path)?)),
+        (None, None) => None,
+    };
+    if let Some(sweep) = sweep {
+        let written = writeln!(
+            out,
+            "{}",
+            style.render(&sweep).expect("seed sweep serializes")
+        );
+        return finish_output(written, &mut out);
+    }
+
+    let mut progress = Progress::new(args.progress && log.warnings(), config);
+    let mut chart = Chart::new(args.chart.as_deref(), args.common.bins, unit);
+
+    if args.summary_only {
+        let aggregate = config
+            .stream(|ep| {
+                progress.tick();
+                chart.push(ep);
+            })
+            .in_unit(unit);
+        progress.finish();
+        chart.print();
+        let written = write!(
+            out,
+            "{}",
+            style.render(&aggregate).expect("aggregate serializes")
+        );
+        finish_output(written, &mut out)?;
+        return check_run(&aggregate, args);
+    }
 
-    let mut elapsed_s = 1800.0 * (1.0 - (0.65 * unlock_rate));
-    elapsed_s *= 1.0 + rng.range(-0.08, 0.05);
-    elapsed_s = clamp(elapsed_s, 80.0, 2000.0);
+    if let Some(every) = args.emit_every {
+        let result = stream_snapshots(
+            config,
+            every,
+            &mut out,
+            &mut progress,
+            &mut chart,
+            style,
+            unit,
+        );
+        progress.finish();
+        chart.print();
+        return match result {
+            Ok(aggregate) => {
+                finish_output(Ok(()), &mut out)?;
+                check_run(&aggregate, args)
+            }
+            Err(err) => finish_output(Err(err), &mut out),
+        };
+    }
 
-    Episode {
-        unlock_rate,
-        objective_complete,
-        stability,
-        elapsed_s,
+    let format = Format::parse(&args.format).unwrap_or(Format::Json);
+    if format == Format::Ndjson {
+        let result = stream_ndjson(
+            config,
+            meta,
+            &mut out,
+            &mut progress,
+            &mut chart,
+            style,
+            unit,
+        );
+        progress.finish();
+        chart.print();
+        return match result {
+            Ok(aggregate) => {
+                finish_output(Ok(()), &mut out)?;
+                check_run(&aggregate, args)
+            }
+            Err(err) => finish_output(Err(err), &mut out),
+        };
     }
+
+    let (rows, aggregate) = config.run_with(|ep| {
+        progress.tick();
+        chart.push(ep);
+    });
+    progress.finish();
+    chart.print();
+    let (rows, aggregate) = in_unit(rows, &aggregate, unit);
+
+    let written = match format {
+        Format::Json => write_json(&mut out, meta, rows, &aggregate, style),
+        Format::Csv => {
+            let result = write_csv(&mut out, &rows, style, unit);
+            eprintln!(
+                "{}",
+                style.render(&aggregate).expect("aggregate serializes")
+            );
+            result
+        }
+        Format::Ndjson => unreachable!("ndjson is streamed above"),
+    };
+    finish_output(written, &mut out)?;
+    check_run(&aggregate, args)
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let episodes = parse_flag_usize(&args, "--episodes", 10);
-    let seed = parse_flag_u64(&args, "--seed", 1);
+fn sweep(args: SweepArgs) -> Result<(), Failure> {
+    let config = args.common.config()?;
+    let axis = |range: Option<ParamRange>, value: f64| range.unwrap_or(ParamRange::fixed(value));
+    let grid = Grid::with_bounds(
+        [
+            axis(args.aggression_range, config.params.aggression),
+            axis(args.greed_range, config.params.greed),
+            axis(args.safety_range, config.params.safety),
+            axis(args.focus_range, config.params.focus),
+        ],
+        config.bounds,
+    )?;
+    let mut out = open_output(args.common.output.as_deref(), args.common.gzip)?;
+    let result = run_sweep(&args, &config, &grid, &mut out);
+    finish_output(result, &mut out)
+}
 
-    let params = Params {
-        aggression: clamp(parse_flag(&args, "--aggression", 0.5), 0.0, 1.0),
-        greed: clamp(parse_flag(&args, "--greed", 0.5), 0.0, 1.0),
-        safety: clamp(parse_flag(&args, "--safety", 0.5), 0.0, 1.0),
-        focus: clamp(parse_flag(&args, "--focus", 0.5), 0.0, 1.0),
+fn optimize(args: OptimizeArgs) -> Result<(), Failure> {
+    let config = args.common.config()?;
+    let target = args.objective();
+    let style = args.common.style();
+    let rendered = match args.method.as_str() {
+        "hillclimb" => style.render(&hill_climb(&config, target, args.step, args.max_iters)),
+        "nelder-mead" => style.render(&nelder_mead(&config, target, args.step, args.max_iters)),
+        "anneal" => style.render(&anneal(
+            &config,
+            target,
+            args.step,
+            args.max_iters,
+            args.schedule(),
+            args.temperature,
+        )),
+        _ => style.render(&random_search(
+            &config,
+            target,
+            args.trials,
+            Sampler::parse(&args.sampler).unwrap_or_default(),
+        )),
     };
+    let mut out = open_output(args.common.output.as_deref(), args.common.gzip)?;
+    let written = writeln!(out, "{}", rendered.expect("search result serializes"));
+    finish_output(written, &mut out)
+}
 
-    let mut rng = Lcg::new(seed);
-    let mut rows: Vec<Episode> = Vec::with_capacity(episodes);
-    let mut objective_sum = 0.0f64;
-    let mut unlock_sum = 0.0f64;
-    let mut stability_sum = 0.0f64;
-    let mut elapsed_sum = 0.0f64;
-
-    for _ in 0..episodes {
-        let ep = run_episode(params, &mut rng);
-        if ep.objective_complete {
-            objective_sum += 1.0;
-        }
-        unlock_sum += ep.unlock_rate;
-        stability_sum += ep.stability;
-        elapsed_sum += ep.elapsed_s;
-        rows.push(ep);
-    }
-
-    let n = episodes as f64;
-    let objective_rate = objective_sum / n;
-    let unlock_rate = unlock_sum / n;
-    let stability_rate = stability_sum / n;
-    let mean_elapsed_s = elapsed_sum / n;
-
-    print!("{{\"episodes\":[");
-    for (idx, ep) in rows.iter().enumerate() {
-        if idx > 0 {
-            print!(",");
-        }
-        let oc = if ep.objective_complete { "true" } else { "false" };
-        print!(
-            "{{\"unlock_rate\":{:.6},\"objective_complete\":{},\"stability\":{:.6},\"elapsed_s\":{:.6}}}",
-            ep.unlock_rate, oc, ep.stability, ep.elapsed_s
+fn compare_sets(args: CompareArgs) -> Result<(), Failure> {
+    let config = args.common.config()?;
+    let b = args.b_params(config.params, config.bounds)?;
+    let comparison = compare(&config, config.params, b);
+    let mut out = open_output(args.common.output.as_deref(), args.common.gzip)?;
+    let written = writeln!(
+        out,
+        "{}",
+        args.common
+            .style()
+            .render(&comparison)
+            .expect("comparison serializes")
+    );
+    finish_output(written, &mut out)
+}
+
+fn run_gradient(args: GradientArgs) -> Result<(), Failure> {
+    let config = args.common.config()?;
+    let result = gradient(&config, args.objective(), args.epsilon);
+    let mut out = open_output(args.common.output.as_deref(), args.common.gzip)?;
+    let written = writeln!(
+        out,
+        "{}",
+        args.common
+            .style()
+            .render(&result)
+            .expect("gradient serializes")
+    );
+    finish_output(written, &mut out)
+}
+
+fn run_pareto(args: ParetoArgs) -> Result<(), Failure> {
+    let config = args.common.config()?;
+    let sampler = Sampler::parse(&args.sampler).unwrap_or_default();
+    let front = pareto(&config, args.trials, sampler);
+    let mut out = open_output(args.common.output.as_deref(), args.common.gzip)?;
+    let written = writeln!(
+        out,
+        "{}",
+        args.common
+            .style()
+            .render(&front)
+            .expect("pareto front serializes")
+    );
+    finish_output(written, &mut out)
+}
+
+fn run_sobol(args: SobolArgs) -> Result<(), Failure> {
+    let config = args.common.config()?;
+    let result = sobol(&config, args.objective(), args.samples);
+    let mut out = open_output(args.common.output.as_deref(), args.common.gzip)?;
+    let written = writeln!(
+        out,
+        "{}",
+        args.common
+            .style()
+            .render(&result)
+            .expect("sobol indices serialize")
+    );
+    finish_output(written, &mut out)
+}
+
+fn run_bench(args: BenchArgs) -> Result<(), Failure> {
+    let config = args.common.config()?;
+    let report = bench(&config);
+    let msg = format!(
+        "{} episodes in {:.3}s ({:.0} episodes/s, rng {})",
+        report.episodes,
+        report.wall.as_secs_f64(),
+        report.episodes_per_s(),
+        args.common.rng
+    );
+    let fields = json!({
+        "episodes": report.episodes,
+        "elapsed_s": report.wall.as_secs_f64(),
+        "episodes_per_s": report.episodes_per_s(),
+        "rng": args.common.rng,
+    });
+    log::emit(Severity::Info, "bench", &msg, fields);
+    Ok(())
+}
+
+fn run_diff(args: DiffArgs) -> Result<(), Failure> {
+    let old = load_aggregate(&args.old)?;
+    let new = load_aggregate(&args.new)?;
+    let metrics = diff(&old, &new, args.threshold);
+    if args.log.level().warnings() {
+        let flagged = metrics.iter().filter(|metric| metric.flagged).count();
+        let msg = format!(
+            "{} of {} metrics moved by more than {}",
+            flagged,
+            metrics.len(),
+            args.threshold
         );
+        let fields = json!({
+            "flagged": flagged,
+            "metrics": metrics.len(),
+            "threshold": args.threshold,
+        });
+        log::emit(Severity::Info, "diff", &msg, fields);
     }
-    print!(
-        "],\"aggregate\":{{\"episodes\":{},\"objective_rate\":{:.6},\"unlock_rate\":{:.6},\"stability_rate\":{:.6},\"mean_elapsed_s\":{:.6}}}}}",
-        episodes, objective_rate, unlock_rate, stability_rate, mean_elapsed_s
+    let mut out = open_output(args.output.as_deref(), false)?;
+    let written = writeln!(
+        out,
+        "{}",
+        args.style().render(&metrics).expect("diff serializes")
+    );
+    finish_output(written, &mut out)
+}
+
+fn run_merge(args: MergeArgs) -> Result<(), Failure> {
+    let parts = args
+        .files
+        .iter()
+        .map(|path| {
+            let part = load_aggregate(path)?;
+            match part.objective_hits {
+                Some(_) => Ok(part),
+                None => Err(format!(
+                    "{} has no objective_hits count (written by an older sim-core); rerun it to merge",
+                    path.display()
+                )),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let merged = merge(&parts, args.ci_level)?;
+    let mut out = open_output(args.output.as_deref(), false)?;
+    let written = writeln!(
+        out,
+        "{}",
+        args.style().render(&merged).expect("aggregate serializes")
     );
+    finish_output(written, &mut out)
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Run(cli.run));
+    if let Err(msg) = command.check_bounds() {
+        Cli::command().error(ErrorKind::ValueValidation, msg).exit();
+    }
+    log::set_format(command.log().format());
+    let level = command.log().level();
+    let started = Instant::now();
+    let result = match command {
+        Command::Run(args) => run(args),
+        Command::Sweep(args) => sweep(args),
+        Command::Optimize(args) => optimize(args),
+        Command::Compare(args) => compare_sets(args),
+        Command::Gradient(args) => run_gradient(args),
+        Command::Pareto(args) => run_pareto(args),
+        Command::Sobol(args) => run_sobol(args),
+        Command::Bench(args) => run_bench(args),
+        Command::Diff(args) => run_diff(args),
+        Command::Merge(args) => run_merge(args),
+    };
+    if level.verbose() {
+        let secs = started.elapsed().as_secs_f64();
+        let msg = format!("finished in {:.3}s", secs);
+        log::emit(Severity::Debug, "verbose", &msg, json!({"elapsed_s": secs}));
+    }
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            log::emit(
+                Severity::Error,
+                failure.tag(),
+                &failure.message(),
+                Value::Null,
+            );
+            failure.exit_code()
+        }
+    }
 }
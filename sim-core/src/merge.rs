@@ -0,0 +1,24 @@
+use crate::{Accumulator, Aggregate};
+
+/// Combines aggregates of disjoint runs into the one a single run over all
+/// their episodes would give: counts add, means and the variance, skewness,
+/// kurtosis and correlation use the parallel (Chan et al.) merge, and the
+/// Wilson interval is recomputed at `ci_level`. `filtered_out` adds up
+/// over the parts that have it. Fields that need the rows
+/// (quantiles, medians, histogram, bootstrap) or a per-episode pass
+/// (covariance) come out `None`. Fails on a part without `objective_hits`,
+/// naming its index.
+pub fn merge(parts: &[Aggregate], ci_level: f64) -> Result<Aggregate, String> {
+    let mut total = Accumulator::new();
+    for (index, part) in parts.iter().enumerate() {
+        let part =
+            Accumulator::from_aggregate(part).map_err(|err| format!("part {}: {}", index, err))?;
+        total.merge(&part);
+    }
+    let filtered = parts.iter().filter_map(|part| part.filtered_out);
+    Ok(Aggregate {
+        filtered_out: filtered.reduce(|a, b| a + b),
+        truncated: parts.iter().any(|part| part.truncated),
+        ..total.finish_with_ci(ci_level)
+    })
+}
@@ -0,0 +1,540 @@
+use serde::{Deserialize, Serialize};
+
+use crate::run::stream_seed;
+use crate::sample::Sampler;
+use crate::{
+    score_config, Aggregate, ParamBounds, Params, Rng, RunConfig, ELAPSED_MAX_S, ELAPSED_MIN_S,
+};
+
+/// Aggregate metric an optimizer maximizes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    ObjectiveRate,
+    StabilityRate,
+    UnlockRate,
+}
+
+impl Target {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "objective_rate" => Some(Self::ObjectiveRate),
+            "stability_rate" => Some(Self::StabilityRate),
+            "unlock_rate" => Some(Self::UnlockRate),
+            _ => None,
+        }
+    }
+
+    pub fn score(self, aggregate: &Aggregate) -> f64 {
+        Objective::from(self).score(aggregate)
+    }
+}
+
+/// Scalar a run is reduced to: one of the `Target` rates, or a weighted sum
+/// of all three and the normalized mean elapsed time.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    ObjectiveRate,
+    StabilityRate,
+    UnlockRate,
+    Weighted(Weights),
+}
+
+/// Coefficients of `Objective::Weighted`; any sign is allowed, and a
+/// negative `mean_elapsed` penalizes slow runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Weights {
+    pub objective_rate: f64,
+    pub stability_rate: f64,
+    pub unlock_rate: f64,
+    /// Applies to `normalized_elapsed(mean_elapsed_s)`.
+    pub mean_elapsed: f64,
+}
+
+impl Weights {
+    /// Parses `name=weight` pairs separated by commas, e.g.
+    /// `objective_rate=1,mean_elapsed=-0.5`; unnamed weights are `0`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut weights = Self::default();
+        for pair in text.split(',') {
+            let (name, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("weight {:?} is not name=value", pair))?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|v: &f64| v.is_finite())
+                .ok_or_else(|| format!("invalid weight {:?} for {}", value, name.trim()))?;
+            let slot = match name.trim() {
+                "objective_rate" => &mut weights.objective_rate,
+                "stability_rate" => &mut weights.stability_rate,
+                "unlock_rate" => &mut weights.unlock_rate,
+                "mean_elapsed" => &mut weights.mean_elapsed,
+                other => {
+                    return Err(format!(
+                        "unknown weight {:?}; expected objective_rate, stability_rate, \
+                         unlock_rate or mean_elapsed",
+                        other
+                    ))
+                }
+            };
+            *slot = value;
+        }
+        Ok(weights)
+    }
+}
+
+/// `elapsed_s` mapped linearly from the model's clamp range
+/// `[ELAPSED_MIN_S, ELAPSED_MAX_S]` onto `[0, 1]`, so the fastest possible
+/// run is `0` and the slowest `1` whatever the parameters.
+pub fn normalized_elapsed(elapsed_s: f64) -> f64 {
+    (elapsed_s - ELAPSED_MIN_S) / (ELAPSED_MAX_S - ELAPSED_MIN_S)
+}
+
+impl Objective {
+    /// The one place a run's aggregate becomes a score.
+    pub fn score(self, aggregate: &Aggregate) -> f64 {
+        match self {
+            Self::ObjectiveRate => aggregate.objective_rate,
+            Self::StabilityRate => aggregate.stability_rate,
+            Self::UnlockRate => aggregate.unlock_rate,
+            Self::Weighted(w) => {
+                w.objective_rate * aggregate.objective_rate
+                    + w.stability_rate * aggregate.stability_rate
+                    + w.unlock_rate * aggregate.unlock_rate
+                    + w.mean_elapsed * normalized_elapsed(aggregate.mean_elapsed_s)
+            }
+        }
+    }
+}
+
+impl From<Target> for Objective {
+    fn from(target: Target) -> Self {
+        match target {
+            Target::ObjectiveRate => Self::ObjectiveRate,
+            Target::StabilityRate => Self::StabilityRate,
+            Target::UnlockRate => Self::UnlockRate,
+        }
+    }
+}
+
+/// One evaluated candidate.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Trial {
+    pub params: Params,
+    pub score: f64,
+    pub aggregate: Aggregate,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub target: Objective,
+    pub sampler: Sampler,
+    pub trials: usize,
+    pub best: Trial,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HillClimbResult {
+    pub target: Objective,
+    pub iterations: usize,
+    pub evaluations: usize,
+    pub best: Trial,
+    /// Best score after each iteration, starting with the initial point.
+    pub trajectory: Vec<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NelderMeadResult {
+    pub target: Objective,
+    pub iterations: usize,
+    pub evaluations: usize,
+    /// Centroid of the final simplex, evaluated like any other point.
+    pub centroid: Trial,
+    /// Best vertex of the final simplex.
+    pub best: Trial,
+    /// Best vertex score after each iteration, starting with the initial simplex.
+    pub trajectory: Vec<f64>,
+}
+
+/// How the annealing temperature falls from its start over `max_iters`
+/// proposals, as a function of the progress `r = k / max_iters`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Schedule {
+    /// `t0 * ANNEAL_FLOOR^r`: the same fraction each step, ending at
+    /// `ANNEAL_FLOOR * t0`.
+    #[default]
+    Exponential,
+    /// `t0 * (1 - r)`, reaching zero with the last proposal.
+    Linear,
+    /// `t0 / ln(e + k)`: the slow classical schedule, still warm at the end.
+    Logarithmic,
+}
+
+impl Schedule {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "exponential" => Some(Self::Exponential),
+            "linear" => Some(Self::Linear),
+            "log" => Some(Self::Logarithmic),
+            _ => None,
+        }
+    }
+
+    /// Temperature for proposal `k` of `iters`, starting from `t0`.
+    pub fn temperature(self, t0: f64, k: usize, iters: usize) -> f64 {
+        let r = k as f64 / iters.max(1) as f64;
+        match self {
+            Self::Exponential => t0 * ANNEAL_FLOOR.powf(r),
+            Self::Linear => t0 * (1.0 - r),
+            Self::Logarithmic => t0 / (std::f64::consts::E + k as f64).ln(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnnealResult {
+    pub target: Objective,
+    pub schedule: Schedule,
+    /// Starting temperature.
+    pub temperature: f64,
+    pub iterations: usize,
+    pub evaluations: usize,
+    /// Proposals the walk moved to, uphill or not.
+    pub accepted: usize,
+    /// Best point seen anywhere on the walk, not necessarily its end.
+    pub best: Trial,
+    /// Best score after each iteration, starting with the initial point.
+    pub trajectory: Vec<f64>,
+}
+
+/// Stream id for the generator behind annealing proposals and acceptances.
+const ANNEAL_STREAM: u64 = 0xa22ea1;
+/// Fraction of the starting temperature the exponential schedule ends at.
+pub const ANNEAL_FLOOR: f64 = 1e-3;
+
+/// Nelder-Mead stops once every vertex scores within this of the best.
+const NM_TOLERANCE: f64 = 1e-9;
+const NM_REFLECT: f64 = 1.0;
+const NM_EXPAND: f64 = 2.0;
+const NM_CONTRACT: f64 = 0.5;
+const NM_SHRINK: f64 = 0.5;
+
+/// Evaluates `params` with every other setting (seed, episodes, ...) taken
+/// from `base`, so all candidates share the same random numbers. Goes
+/// through `score_config`, so no rows are kept.
+pub fn evaluate(base: &RunConfig, params: Params, target: Objective) -> Trial {
+    let (score, aggregate) = score_config(&RunConfig { params, ..*base }, target);
+    Trial {
+        params,
+        score,
+        aggregate,
+    }
+}
+
+/// Evaluates the first `trials` points of `sampler` in `[0,1]^4`, scaled onto
+/// `base.bounds` (random points come from a generator seeded by
+/// `base.seed`), and keeps the best.
+/// Ties go to the earlier trial.
+pub fn random_search(
+    base: &RunConfig,
+    target: Objective,
+    trials: usize,
+    sampler: Sampler,
+) -> SearchResult {
+    let mut points = sampler.points(base.seed, trials.max(1));
+    let mut best: Option<Trial> = None;
+    for unit in points.by_ref().take(trials.max(1)) {
+        let trial = evaluate(base, unit.map(|u| base.bounds.scale(u)), target);
+        if best.as_ref().is_none_or(|b| trial.score > b.score) {
+            best = Some(trial);
+        }
+    }
+    SearchResult {
+        target,
+        sampler,
+        trials: trials.max(1),
+        best: best.expect("at least one trial"),
+    }
+}
+
+/// Greedy local search from `base.params`: each iteration evaluates the eight
+/// +/- `step` neighbours (clamped into `base.bounds`) and moves to the best one,
+/// stopping when none improves or after `max_iters` moves.
+pub fn hill_climb(
+    base: &RunConfig,
+    target: Objective,
+    step: f64,
+    max_iters: usize,
+) -> HillClimbResult {
+    let mut best = evaluate(base, base.params, target);
+    let mut evaluations = 1usize;
+    let mut iterations = 0usize;
+    let mut trajectory = vec![best.score];
+
+    while iterations < max_iters {
+        let mut improved: Option<Trial> = None;
+        for candidate in neighbours(best.params, step, base.bounds) {
+            let trial = evaluate(base, candidate, target);
+            evaluations += 1;
+            let leader = improved.as_ref().unwrap_or(&best);
+            if trial.score > leader.score {
+                improved = Some(trial);
+            }
+        }
+        match improved {
+            Some(trial) => {
+                best = trial;
+                iterations += 1;
+                trajectory.push(best.score);
+            }
+            None => break,
+        }
+    }
+
+    HillClimbResult {
+        target,
+        iterations,
+        evaluations,
+        best,
+        trajectory,
+    }
+}
+
+/// Nelder-Mead simplex search maximizing `target` (minimizing its negative)
+/// from `base.params`. The initial simplex adds `step` to one axis per
+/// vertex (subtracting it where that would leave `base.bounds`); each
+/// iteration reflects the worst vertex through the centroid of the rest,
+/// then expands, contracts or shrinks towards the best. Every vertex is
+/// clamped into `base.bounds`. Stops when the vertex scores agree within
+/// `NM_TOLERANCE` or after `max_iters` iterations.
+pub fn nelder_mead(
+    base: &RunConfig,
+    target: Objective,
+    step: f64,
+    max_iters: usize,
+) -> NelderMeadResult {
+    let bounds = base.bounds;
+    let mut evaluations = 0usize;
+    let mut eval = |params: Params| {
+        evaluations += 1;
+        evaluate(base, params, target)
+    };
+    let mut simplex = vec![eval(base.params)];
+    for dim in 0..4 {
+        let mut p = base.params;
+        let field = axis_mut(&mut p, dim);
+        *field = if *field + step <= bounds.max {
+            *field + step
+        } else {
+            bounds.clamp(*field - step)
+        };
+        simplex.push(eval(p));
+    }
+
+    let by_score = |a: &Trial, b: &Trial| b.score.total_cmp(&a.score);
+    simplex.sort_by(by_score);
+    let mut iterations = 0usize;
+    let mut trajectory = vec![simplex[0].score];
+    while iterations < max_iters && simplex[0].score - simplex[4].score > NM_TOLERANCE {
+        let c = centroid(simplex[..4].iter().map(|t| t.params));
+        let worst = simplex[4].params;
+        let reflected = eval(toward(c, worst, -NM_REFLECT, bounds));
+        if reflected.score > simplex[0].score {
+            let expanded = eval(toward(c, reflected.params, NM_EXPAND, bounds));
+            simplex[4] = if expanded.score > reflected.score {
+                expanded
+            } else {
+                reflected
+            };
+        } else if reflected.score > simplex[3].score {
+            simplex[4] = reflected;
+        } else {
+            let outside = reflected.score > simplex[4].score;
+            let contracted = if outside {
+                eval(toward(c, reflected.params, NM_CONTRACT, bounds))
+            } else {
+                eval(toward(c, worst, NM_CONTRACT, bounds))
+            };
+            let floor = if outside {
+                reflected.score
+            } else {
+                simplex[4].score
+            };
+            if contracted.score >= floor {
+                simplex[4] = contracted;
+            } else {
+                let best = simplex[0].params;
+                for vertex in &mut simplex[1..] {
+                    *vertex = eval(toward(best, vertex.params, NM_SHRINK, bounds));
+                }
+            }
+        }
+        simplex.sort_by(by_score);
+        iterations += 1;
+        trajectory.push(simplex[0].score);
+    }
+
+    let centroid = eval(centroid(simplex.iter().map(|t| t.params)));
+    NelderMeadResult {
+        target,
+        iterations,
+        evaluations,
+        centroid,
+        best: simplex.swap_remove(0),
+        trajectory,
+    }
+}
+
+/// Simulated annealing from `base.params` for `max_iters` proposals. Each
+/// proposal moves every axis by a uniform offset in `+/- step` (clamped into
+/// `base.bounds`); a better candidate is always taken and a worse one with
+/// probability `exp(-delta / T)`, `delta` the score drop and `T` the
+/// `schedule` temperature from `temperature`, so the walk can leave a local
+/// optimum while it is warm. Proposals and acceptances come from the
+/// `base.rng` generator on the base seed, so a search is reproducible.
+pub fn anneal(
+    base: &RunConfig,
+    target: Objective,
+    step: f64,
+    max_iters: usize,
+    schedule: Schedule,
+    temperature: f64,
+) -> AnnealResult {
+    let mut rng = base.rng.seeded(stream_seed(base.seed, ANNEAL_STREAM));
+    let mut current = evaluate(base, base.params, target);
+    let mut best = current.clone();
+    let mut accepted = 0usize;
+    let mut trajectory = vec![best.score];
+    for k in 0..max_iters {
+        let mut candidate = current.params;
+        for dim in 0..4 {
+            let field = axis_mut(&mut candidate, dim);
+            *field = base
+                .bounds
+                .clamp(*field + step * (2.0 * rng.next_f64() - 1.0));
+        }
+        let trial = evaluate(base, candidate, target);
+        let delta = current.score - trial.score;
+        let t = schedule.temperature(temperature, k, max_iters);
+        let uphill = delta <= 0.0;
+        // Always draw, so the stream doesn't depend on which moves went uphill.
+        let u = rng.next_f64();
+        if uphill || (t > 0.0 && u < (-delta / t).exp()) {
+            accepted += 1;
+            current = trial;
+            if current.score > best.score {
+                best = current.clone();
+            }
+        }
+        trajectory.push(best.score);
+    }
+    AnnealResult {
+        target,
+        schedule,
+        temperature,
+        iterations: max_iters,
+        evaluations: max_iters + 1,
+        accepted,
+        best,
+        trajectory,
+    }
+}
+
+/// `from + t * (to - from)` on every axis, clamped into `bounds`.
+fn toward(from: Params, mut to: Params, t: f64, bounds: ParamBounds) -> Params {
+    let mut p = from;
+    for dim in 0..4 {
+        let target = *axis_mut(&mut to, dim);
+        let field = axis_mut(&mut p, dim);
+        *field = bounds.clamp(*field + t * (target - *field));
+    }
+    p
+}
+
+fn centroid(points: impl Iterator<Item = Params>) -> Params {
+    let mut sum = Params {
+        aggression: 0.0,
+        greed: 0.0,
+        safety: 0.0,
+        focus: 0.0,
+    };
+    let mut count = 0usize;
+    for p in points {
+        sum.aggression += p.aggression;
+        sum.greed += p.greed;
+        sum.safety += p.safety;
+        sum.focus += p.focus;
+        count += 1;
+    }
+    sum.map(|x| x / count as f64)
+}
+
+fn neighbours(center: Params, step: f64, bounds: ParamBounds) -> Vec<Params> {
+    let mut out = Vec::with_capacity(8);
+    for dim in 0..4 {
+        for delta in [step, -step] {
+            let mut p = center;
+            let field = axis_mut(&mut p, dim);
+            *field = bounds.clamp(*field + delta);
+            if p != center {
+                out.push(p);
+            }
+        }
+    }
+    out
+}
+
+/// Parameter `dim` in the order aggression, greed, safety, focus.
+pub(crate) fn axis_mut(params: &mut Params, dim: usize) -> &mut f64 {
+    match dim {
+        0 => &mut params.aggression,
+        1 => &mut params.greed,
+        2 => &mut params.safety,
+        _ => &mut params.focus,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Gradient {
+    pub target: Objective,
+    pub params: Params,
+    pub epsilon: f64,
+    /// Score at `params` itself.
+    pub score: f64,
+    /// d score / d parameter, keyed like `Params`.
+    pub partials: Params,
+}
+
+/// Central-difference gradient of `target` at `base.params`: each axis is
+/// evaluated at `+/- epsilon` (clamped to `base.bounds`, so one-sided at a bound)
+/// over `base.episodes` with the same seed, so the two sides share their
+/// random numbers and most of the noise cancels.
+pub fn gradient(base: &RunConfig, target: Objective, epsilon: f64) -> Gradient {
+    let center = base.params;
+    let mut partials = center;
+    for dim in 0..4 {
+        let at = |value: f64| {
+            let mut p = center;
+            *axis_mut(&mut p, dim) = value;
+            evaluate(base, p, target).score
+        };
+        let x = *axis_mut(&mut partials, dim);
+        let (lo, hi) = (
+            base.bounds.clamp(x - epsilon),
+            base.bounds.clamp(x + epsilon),
+        );
+        *axis_mut(&mut partials, dim) = (at(hi) - at(lo)) / (hi - lo);
+    }
+    Gradient {
+        target,
+        params: center,
+        epsilon,
+        score: evaluate(base, center, target).score,
+        partials,
+    }
+}
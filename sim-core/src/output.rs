@@ -0,0 +1,307 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+use crate::stats::Histogram;
+use crate::{Aggregate, Episode, FailReason};
+
+pub const DEFAULT_DECIMALS: u32 = 6;
+/// Most decimals `--precision` accepts; 17 significant digits round-trip any `f64`.
+pub const MAX_DECIMALS: u32 = 17;
+
+/// Longest bar `chart` draws; the fullest bucket gets all of it.
+pub const CHART_WIDTH: usize = 50;
+
+pub const CSV_HEADER: &str =
+    "unlock_rate,objective_complete,stability,elapsed_s,fail_reason,outlier";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+    Ndjson,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            "ndjson" => Some(Self::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+pub fn round_to(v: f64, decimals: u32) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    let scaled = v * factor;
+    // Past 2^53 every f64 is an integer, so `v` has no finer digits to
+    // drop and the round trip through `scaled` would only add error.
+    if scaled.abs() >= (1u64 << 53) as f64 {
+        return v;
+    }
+    scaled.round() / factor
+}
+
+/// Rounds every float inside `value` to `decimals` places, leaving integers untouched.
+pub fn round_floats(value: &mut Value, decimals: u32) {
+    match value {
+        Value::Number(n) if n.is_f64() => {
+            let rounded = n.as_f64().map(|v| round_to(v, decimals));
+            *value = rounded
+                .and_then(Number::from_f64)
+                .map(Value::Number)
+                .unwrap_or(Value::Null);
+        }
+        Value::Array(items) => {
+            for item in items {
+                round_floats(item, decimals);
+            }
+        }
+        Value::Object(map) => {
+            for (_, item) in map.iter_mut() {
+                round_floats(item, decimals);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// How output is written: indented JSON or not (`--pretty`), and the
+/// decimals every float keeps (`--precision`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Style {
+    pub pretty: bool,
+    pub decimals: u32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            pretty: false,
+            decimals: DEFAULT_DECIMALS,
+        }
+    }
+}
+
+impl Style {
+    fn rounded_tree<T: Serialize>(self, value: &T) -> serde_json::Result<Value> {
+        let mut tree = serde_json::to_value(value)?;
+        round_floats(&mut tree, self.decimals);
+        Ok(tree)
+    }
+
+    /// `value` as JSON, indented if `pretty`.
+    pub fn render<T: Serialize>(self, value: &T) -> serde_json::Result<String> {
+        let tree = self.rounded_tree(value)?;
+        if self.pretty {
+            serde_json::to_string_pretty(&tree)
+        } else {
+            serde_json::to_string(&tree)
+        }
+    }
+
+    /// `value` as one line of JSON whatever `pretty` says, for NDJSON.
+    pub fn line<T: Serialize>(self, value: &T) -> serde_json::Result<String> {
+        serde_json::to_string(&self.rounded_tree(value)?)
+    }
+
+    /// One CSV line under `CSV_HEADER`, plus a `compute_ns` column when
+    /// the episode was timed.
+    pub fn csv_row(self, ep: &Episode) -> String {
+        let d = self.decimals as usize;
+        let row = format!(
+            "{:.d$},{},{:.d$},{:.d$},{},{}",
+            ep.unlock_rate,
+            ep.objective_complete,
+            ep.stability,
+            ep.elapsed_s,
+            ep.fail_reason.map_or("", FailReason::as_str),
+            ep.outlier
+        );
+        match ep.compute_ns {
+            Some(ns) => format!("{},{}", row, ns),
+            None => row,
+        }
+    }
+}
+
+pub fn to_json<T: Serialize>(value: &T) -> serde_json::Result<String> {
+    Style::default().line(value)
+}
+
+pub fn to_json_pretty<T: Serialize>(value: &T) -> serde_json::Result<String> {
+    Style {
+        pretty: true,
+        ..Style::default()
+    }
+    .render(value)
+}
+
+pub fn csv_row(ep: &Episode) -> String {
+    Style::default().csv_row(ep)
+}
+
+/// `histogram` as text for a terminal: a title line, then one `lo - hi |`
+/// row per bucket with a `#` bar scaled to `width` and the count. Nonempty
+/// buckets always get at least one `#`.
+pub fn chart(histogram: &Histogram, width: usize) -> String {
+    let total: usize = histogram.counts.iter().sum();
+    let peak = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+    let labels: Vec<(String, String)> = histogram
+        .edges
+        .windows(2)
+        .map(|edge| (format!("{:.4}", edge[0]), format!("{:.4}", edge[1])))
+        .collect();
+    let lo_width = labels.iter().map(|(lo, _)| lo.len()).max().unwrap_or(0);
+    let hi_width = labels.iter().map(|(_, hi)| hi.len()).max().unwrap_or(0);
+    let mut text = format!(
+        "{}: {} episodes in {} bins\n",
+        histogram.metric.as_str(),
+        total,
+        histogram.counts.len()
+    );
+    for ((lo, hi), &count) in labels.iter().zip(&histogram.counts) {
+        let bar = match count {
+            0 => 0,
+            _ => (count * width / peak).max(1),
+        };
+        text += &format!(
+            "{:>lo_width$} - {:>hi_width$} | {:<width$} {}\n",
+            lo,
+            hi,
+            "#".repeat(bar),
+            count
+        );
+    }
+    text
+}
+
+/// `secs` after the Unix epoch as ISO-8601 UTC, e.g. `2024-03-01T12:00:00Z`.
+pub fn utc_timestamp(secs: u64) -> String {
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // Days to a proleptic Gregorian date, counted in 400-year eras from
+    // 0000-03-01 so the leap day falls at the end of each year.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+const BOLD: &str = "\x1b[1m";
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// The headline fields of `aggregate` as an aligned two-column table for a
+/// terminal. With `fail_under`, the `objective_rate` row says whether it
+/// passed; `color` adds ANSI bold headers and a green or red verdict.
+pub fn table(aggregate: &Aggregate, fail_under: Option<f64>, color: bool) -> String {
+    let paint = |code: &str, text: String| {
+        if color {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text
+        }
+    };
+    let mut objective = format!(
+        "{:.4} [{:.4}, {:.4}]",
+        aggregate.objective_rate,
+        aggregate.objective_rate_wilson_lo,
+        aggregate.objective_rate_wilson_hi
+    );
+    if let Some(threshold) = fail_under {
+        let verdict = if aggregate.objective_rate >= threshold {
+            paint(GREEN, format!("pass (>= {})", threshold))
+        } else {
+            paint(RED, format!("FAIL (< {})", threshold))
+        };
+        objective = format!("{} {}", objective, verdict);
+    }
+    let mut rows = vec![
+        ("episodes", aggregate.episodes.to_string()),
+        ("objective_rate", objective),
+        ("unlock_rate", format!("{:.4}", aggregate.unlock_rate)),
+        ("stability_rate", format!("{:.4}", aggregate.stability_rate)),
+        (
+            "mean_elapsed_s",
+            format!(
+                "{:.4} ± {:.4}",
+                aggregate.mean_elapsed_s, aggregate.elapsed_std
+            ),
+        ),
+    ];
+    let quantiles = [
+        ("elapsed_p50", aggregate.elapsed_p50),
+        ("elapsed_p90", aggregate.elapsed_p90),
+        ("elapsed_p99", aggregate.elapsed_p99),
+    ];
+    for (name, value) in quantiles {
+        if let Some(value) = value {
+            rows.push((name, format!("{:.4}", value)));
+        }
+    }
+    if aggregate.truncated {
+        rows.push(("truncated", "true".to_string()));
+    }
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let mut text = paint(BOLD, format!("{:<width$}  value", "metric")) + "\n";
+    for (name, value) in rows {
+        text += &format!("{:<width$}  {}\n", name, value);
+    }
+    text
+}
+
+/// Reads the aggregate back from a saved `json` (plain or `--pretty`),
+/// `--summary-only` or `ndjson` output; a `.gz` file is decompressed first.
+pub fn load_aggregate(path: &Path) -> Result<Aggregate, String> {
+    let mut text = String::new();
+    let read = File::open(path).and_then(|mut file| {
+        if path.extension().is_some_and(|ext| ext == "gz") {
+            GzDecoder::new(file).read_to_string(&mut text)
+        } else {
+            file.read_to_string(&mut text)
+        }
+    });
+    read.map_err(|err| format!("cannot read output {}: {}", path.display(), err))?;
+    parse_aggregate(&text).map_err(|err| format!("invalid output {}: {}", path.display(), err))
+}
+
+/// The `aggregate` of a run output object, the object itself for
+/// `--summary-only`, or the last line of `ndjson`.
+pub fn parse_aggregate(text: &str) -> Result<Aggregate, String> {
+    let tree: Value = match serde_json::from_str(text) {
+        Ok(tree) => tree,
+        Err(err) => {
+            let last = text.lines().rev().find(|line| !line.trim().is_empty());
+            match last.map(serde_json::from_str) {
+                Some(Ok(tree)) => tree,
+                _ => return Err(err.to_string()),
+            }
+        }
+    };
+    let aggregate = match tree {
+        Value::Object(mut map) => map.remove("aggregate").unwrap_or(Value::Object(map)),
+        tree => tree,
+    };
+    serde_json::from_value(aggregate).map_err(|err| err.to_string())
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+use crate::sample::Sampler;
+use crate::{Params, RunConfig};
+
+/// One sampled parameter set and the two rates it trades off.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParetoPoint {
+    pub params: Params,
+    pub unlock_rate: f64,
+    pub stability_rate: f64,
+}
+
+impl ParetoPoint {
+    /// At least as good on both rates and strictly better on one.
+    pub fn dominates(&self, other: &ParetoPoint) -> bool {
+        self.unlock_rate >= other.unlock_rate
+            && self.stability_rate >= other.stability_rate
+            && (self.unlock_rate > other.unlock_rate || self.stability_rate > other.stability_rate)
+    }
+}
+
+/// Streams `base.episodes` at each of the first `trials` points of
+/// `sampler` (scaled onto `base.bounds`, all on the base seed) and returns
+/// the non-dominated ones, ordered by rising `unlock_rate`.
+pub fn pareto(base: &RunConfig, trials: usize, sampler: Sampler) -> Vec<ParetoPoint> {
+    let trials = trials.max(1);
+    let points: Vec<ParetoPoint> = sampler
+        .points(base.seed, trials)
+        .take(trials)
+        .map(|unit| {
+            let params = unit.map(|u| base.bounds.scale(u));
+            let aggregate = RunConfig { params, ..*base }.stream(|_| {});
+            ParetoPoint {
+                params,
+                unlock_rate: aggregate.unlock_rate,
+                stability_rate: aggregate.stability_rate,
+            }
+        })
+        .collect();
+    frontier(&points)
+}
+
+/// The points no other point dominates, by the O(n^2) pairwise check.
+/// Exact ties keep every copy. Sorted by `unlock_rate`, so stability falls
+/// along the result.
+pub fn frontier(points: &[ParetoPoint]) -> Vec<ParetoPoint> {
+    let mut front: Vec<ParetoPoint> = points
+        .iter()
+        .filter(|p| !points.iter().any(|q| q.dominates(p)))
+        .copied()
+        .collect();
+    front.sort_by(|a, b| a.unlock_rate.total_cmp(&b.unlock_rate));
+    front
+}
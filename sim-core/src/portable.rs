@@ -0,0 +1,67 @@
+//! Transcendentals built only from `+ - * /`, `sqrt` and bit manipulation,
+//! all of which IEEE 754 rounds exactly. Platform `libm` versions of `ln`
+//! and `cos` may differ in the last bit; these give the same bits on every
+//! target. Rust never fuses multiply-adds on its own, so evaluation order
+//! is fixed by the source.
+
+use std::f64::consts::{FRAC_PI_2, LN_2, SQRT_2, TAU};
+
+use crate::Rng;
+
+/// `FRAC_PI_2` split so `k * PI_2_HI` is exact for the small `k` used here.
+const PI_2_HI: f64 = 1.570_796_326_734_125_6;
+const PI_2_LO: f64 = 6.077_100_506_506_192e-11;
+
+/// Natural logarithm for finite `x > 0`.
+pub fn ln(x: f64) -> f64 {
+    debug_assert!(x > 0.0 && x.is_finite(), "portable::ln({})", x);
+    let (mut x, mut exponent) = (x, 0i64);
+    if x < f64::MIN_POSITIVE {
+        x *= (1u64 << 54) as f64;
+        exponent -= 54;
+    }
+    let bits = x.to_bits();
+    exponent += ((bits >> 52) & 0x7ff) as i64 - 1023;
+    // Mantissa in [1, 2), then folded into [sqrt(2)/2, sqrt(2)).
+    let mut m = f64::from_bits((bits & ((1u64 << 52) - 1)) | (1023u64 << 52));
+    if m > SQRT_2 {
+        m /= 2.0;
+        exponent += 1;
+    }
+    // ln(m) = 2 atanh(s) with s = (m - 1) / (m + 1), |s| < 0.172.
+    let s = (m - 1.0) / (m + 1.0);
+    let s2 = s * s;
+    let series = (0..13)
+        .rev()
+        .fold(0.0, |acc, k| acc * s2 + 1.0 / (2 * k + 1) as f64);
+    exponent as f64 * LN_2 + 2.0 * s * series
+}
+
+/// `(sin x, cos x)`, accurate for `|x|` up to about `2^20`.
+pub fn sin_cos(x: f64) -> (f64, f64) {
+    let k = (x / FRAC_PI_2).round();
+    let r = (x - k * PI_2_HI) - k * PI_2_LO;
+    let r2 = r * r;
+    // Taylor series on |r| <= pi/4, truncated past the f64 epsilon.
+    let sin = r
+        * (0..9).rev().fold(0.0, |acc, n| {
+            1.0 - acc * r2 / ((2 * n + 2) * (2 * n + 3)) as f64
+        });
+    let cos = (0..9).rev().fold(0.0, |acc, n| {
+        1.0 - acc * r2 / ((2 * n + 1) * (2 * n + 2)) as f64
+    });
+    match (k as i64).rem_euclid(4) {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    }
+}
+
+/// Box-Muller standard normal from two uniforms, with the portable `ln` and
+/// `cos`; no second value is cached.
+pub fn gaussian<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let u1 = 1.0 - rng.next_f64();
+    let u2 = rng.next_f64();
+    (-2.0 * ln(u1)).sqrt() * sin_cos(TAU * u2).1
+}
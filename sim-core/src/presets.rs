@@ -0,0 +1,41 @@
+//! Named parameter profiles for `--preset`.
+
+use crate::Params;
+
+/// `(name, params)` for every built-in profile.
+pub const PRESETS: [(&str, Params); 3] = [
+    (
+        "aggressive",
+        Params {
+            aggression: 0.9,
+            greed: 0.8,
+            safety: 0.2,
+            focus: 0.6,
+        },
+    ),
+    (
+        "balanced",
+        Params {
+            aggression: 0.5,
+            greed: 0.5,
+            safety: 0.5,
+            focus: 0.5,
+        },
+    ),
+    (
+        "cautious",
+        Params {
+            aggression: 0.2,
+            greed: 0.3,
+            safety: 0.9,
+            focus: 0.5,
+        },
+    ),
+];
+
+pub fn preset(name: &str) -> Option<Params> {
+    PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|&(_, params)| params)
+}
@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+
+/// SplitMix64 finalizer, used to derive well-mixed seeds from small inputs.
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// FNV-1a (64-bit) of `label`, for naming experiments instead of picking
+/// integers. Pure byte arithmetic, so it is stable across platforms.
+pub fn seed_from_str(label: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in label.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Source of random numbers for the model. Only `next_u64` is required;
+/// the other draws are built from it unless a generator overrides them.
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+
+    fn next_f64(&mut self) -> f64 {
+        let x = self.next_u64() >> 11;
+        (x as f64) / ((1u64 << 53) as f64)
+    }
+
+    /// Uniform in `[lo, hi)`; `lo <= hi` is checked in debug builds.
+    fn range(&mut self, lo: f64, hi: f64) -> f64 {
+        debug_assert!(lo <= hi, "range bounds inverted: [{}, {})", lo, hi);
+        lo + (hi - lo) * self.next_f64()
+    }
+
+    /// Standard normal draw. The default spends two uniforms per call;
+    /// generators that can cache the second Box-Muller value should.
+    fn next_gaussian(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+    }
+}
+
+const LCG_MULTIPLIER: u64 = 6364136223846793005;
+const LCG_INCREMENT: u64 = 1442695040888963407;
+
+pub struct Lcg {
+    state: u64,
+    /// Second Box-Muller draw, returned by the next `next_gaussian` call.
+    spare_gaussian: Option<f64>,
+}
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        let initial = if seed == 0 { 0x9e3779b97f4a7c15 } else { seed };
+        Self {
+            state: initial,
+            spare_gaussian: None,
+        }
+    }
+
+    /// The raw 64-bit state, for checkpointing a stream with `from_state`.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// Resumes a stream saved with `state`. Unlike `new`, zero is taken as
+    /// is. A cached Gaussian is not part of the state, so a restored
+    /// generator only matches the original if none was pending.
+    pub fn from_state(state: u64) -> Self {
+        Self {
+            state,
+            spare_gaussian: None,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(LCG_MULTIPLIER)
+            .wrapping_add(LCG_INCREMENT);
+        self.state
+    }
+
+    /// Advances the state as if `next_u64` were called `n` times, in
+    /// `O(log n)`: composes the affine step `x -> a*x + c` with itself by
+    /// repeated squaring. Drops any cached Gaussian.
+    pub fn jump(&mut self, mut n: u64) {
+        let (mut mul, mut add) = (1u64, 0u64);
+        let (mut step_mul, mut step_add) = (LCG_MULTIPLIER, LCG_INCREMENT);
+        while n > 0 {
+            if n & 1 == 1 {
+                mul = mul.wrapping_mul(step_mul);
+                add = add.wrapping_mul(step_mul).wrapping_add(step_add);
+            }
+            step_add = step_add.wrapping_mul(step_mul.wrapping_add(1));
+            step_mul = step_mul.wrapping_mul(step_mul);
+            n >>= 1;
+        }
+        self.state = self.state.wrapping_mul(mul).wrapping_add(add);
+        self.spare_gaussian = None;
+    }
+
+    pub fn next_f64(&mut self) -> f64 {
+        let x = self.next_u64() >> 11;
+        (x as f64) / ((1u64 << 53) as f64)
+    }
+
+    pub fn range(&mut self, lo: f64, hi: f64) -> f64 {
+        debug_assert!(lo <= hi, "range bounds inverted: [{}, {})", lo, hi);
+        lo + (hi - lo) * self.next_f64()
+    }
+
+    /// Standard normal draw (Box-Muller). Each pair of uniforms yields two
+    /// normals; the second is cached and returned by the following call.
+    pub fn next_gaussian(&mut self) -> f64 {
+        if let Some(z) = self.spare_gaussian.take() {
+            return z;
+        }
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        let radius = (-2.0 * u1.ln()).sqrt();
+        let angle = std::f64::consts::TAU * u2;
+        self.spare_gaussian = Some(radius * angle.sin());
+        radius * angle.cos()
+    }
+}
+
+impl Rng for Lcg {
+    fn next_u64(&mut self) -> u64 {
+        Lcg::next_u64(self)
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        Lcg::next_f64(self)
+    }
+
+    fn range(&mut self, lo: f64, hi: f64) -> f64 {
+        Lcg::range(self, lo, hi)
+    }
+
+    fn next_gaussian(&mut self) -> f64 {
+        Lcg::next_gaussian(self)
+    }
+}
+
+const PCG_MULTIPLIER: u128 = 0x2360_ed05_1fc6_5da4_4385_df64_9fcc_f645;
+
+/// PCG XSL-RR 128/64: 128-bit LCG state with a permuted 64-bit output,
+/// which hides the weak low-order bits of the raw `Lcg`.
+pub struct Pcg64 {
+    state: u128,
+    increment: u128,
+    spare_gaussian: Option<f64>,
+}
+
+impl Pcg64 {
+    pub fn new(seed: u64) -> Self {
+        let wide = |x: u64| (u128::from(splitmix64(x)) << 64) | u128::from(splitmix64(!x));
+        let mut rng = Self {
+            state: 0,
+            increment: (wide(seed ^ 0xda3e_39cb_94b9_5bdb) << 1) | 1,
+            spare_gaussian: None,
+        };
+        rng.step();
+        rng.state = rng.state.wrapping_add(wide(seed));
+        rng.step();
+        rng
+    }
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(PCG_MULTIPLIER)
+            .wrapping_add(self.increment);
+    }
+}
+
+impl Rng for Pcg64 {
+    fn next_u64(&mut self) -> u64 {
+        self.step();
+        let rot = (self.state >> 122) as u32;
+        let xsl = ((self.state >> 64) as u64) ^ (self.state as u64);
+        xsl.rotate_right(rot)
+    }
+
+    fn next_gaussian(&mut self) -> f64 {
+        if let Some(z) = self.spare_gaussian.take() {
+            return z;
+        }
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        let radius = (-2.0 * u1.ln()).sqrt();
+        let angle = std::f64::consts::TAU * u2;
+        self.spare_gaussian = Some(radius * angle.sin());
+        radius * angle.cos()
+    }
+}
+
+/// Generator selected at runtime with `--rng`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RngKind {
+    #[default]
+    Lcg,
+    Pcg,
+}
+
+impl RngKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "lcg" => Some(Self::Lcg),
+            "pcg" => Some(Self::Pcg),
+            _ => None,
+        }
+    }
+
+    pub fn seeded(self, seed: u64) -> Generator {
+        match self {
+            Self::Lcg => Generator::Lcg(Lcg::new(seed)),
+            Self::Pcg => Generator::Pcg(Pcg64::new(seed)),
+        }
+    }
+}
+
+pub enum Generator {
+    Lcg(Lcg),
+    Pcg(Pcg64),
+}
+
+impl Rng for Generator {
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            Self::Lcg(rng) => rng.next_u64(),
+            Self::Pcg(rng) => rng.next_u64(),
+        }
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        match self {
+            Self::Lcg(rng) => rng.next_f64(),
+            Self::Pcg(rng) => rng.next_f64(),
+        }
+    }
+
+    fn next_gaussian(&mut self) -> f64 {
+        match self {
+            Self::Lcg(rng) => rng.next_gaussian(),
+            Self::Pcg(rng) => rng.next_gaussian(),
+        }
+    }
+}
+
+/// Passes `inner`'s raw draws through while keeping a copy, so they can be
+/// replayed mirrored by `Mirrored`. Every other draw uses the trait defaults
+/// on top of `next_u64`, so nothing escapes the record.
+pub struct Recording<'a, R: Rng> {
+    inner: &'a mut R,
+    draws: &'a mut Vec<u64>,
+}
+
+impl<'a, R: Rng> Recording<'a, R> {
+    pub fn new(inner: &'a mut R, draws: &'a mut Vec<u64>) -> Self {
+        draws.clear();
+        Self { inner, draws }
+    }
+}
+
+impl<R: Rng> Rng for Recording<'_, R> {
+    fn next_u64(&mut self) -> u64 {
+        let x = self.inner.next_u64();
+        self.draws.push(x);
+        x
+    }
+}
+
+/// Replays recorded draws bit-flipped, so each uniform `u` comes back as
+/// `1 - u - 2^-53` (still in `[0, 1)`): the antithetic partner stream.
+/// Falls back to `spare` if the replay consumes more draws than were recorded.
+pub struct Mirrored<'a, R: Rng> {
+    draws: &'a [u64],
+    next: usize,
+    spare: &'a mut R,
+}
+
+impl<'a, R: Rng> Mirrored<'a, R> {
+    pub fn new(draws: &'a [u64], spare: &'a mut R) -> Self {
+        Self {
+            draws,
+            next: 0,
+            spare,
+        }
+    }
+}
+
+impl<R: Rng> Rng for Mirrored<'_, R> {
+    fn next_u64(&mut self) -> u64 {
+        match self.draws.get(self.next) {
+            Some(&x) => {
+                self.next += 1;
+                !x
+            }
+            None => self.spare.next_u64(),
+        }
+    }
+}
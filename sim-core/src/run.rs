@@ -0,0 +1,558 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::rng::{seed_from_str, splitmix64, Generator, Lcg, Mirrored, Recording, RngKind};
+use crate::stats::{self, Running, COVARIANCE_METRICS};
+use crate::{
+    widen, winsorized, Accumulator, Aggregate, AggregateOptions, Episode, InverseSampling, Model,
+    ParamBounds, Params, SimConfig, VarianceReduction,
+};
+
+/// Episodes each worker runs per round before results are merged in order.
+const BLOCK_PER_WORKER: usize = 1 << 14;
+
+/// Episodes a single-threaded run generates per `run_episodes_into` call.
+const EPISODE_CHUNK: usize = 1024;
+
+/// Kept episodes between `target_ci` checks.
+const ADAPTIVE_BLOCK: usize = 1000;
+
+/// Kept episodes between `time_budget` clock reads.
+const TIME_CHECK_BLOCK: usize = 1024;
+
+/// Stream id used for the bootstrap resampling generator.
+const BOOTSTRAP_STREAM: u64 = u64::MAX;
+
+/// Seed for the `stream`-th generator derived from `seed`. Stream 0 is the
+/// seed itself, so single-threaded runs keep their historical output.
+pub fn stream_seed(seed: u64, stream: u64) -> u64 {
+    if stream == 0 {
+        seed
+    } else {
+        splitmix64(seed ^ splitmix64(stream))
+    }
+}
+
+/// Seed for episode `index` in `--independent-seeds` mode, so any single
+/// episode can be rerun with `run_episode_seeded` without replaying the rest.
+pub fn episode_seed(base: u64, index: usize) -> u64 {
+    splitmix64(splitmix64(base) ^ index as u64)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RunConfig {
+    pub params: Params,
+    pub seed: u64,
+    pub episodes: usize,
+    /// Episodes run before the first kept one. They advance the generators
+    /// (so later episodes differ from a no-warmup run) but are excluded from
+    /// the rows and the aggregate.
+    pub warmup: usize,
+    /// Worker threads. Worker `t` owns the generator seeded with
+    /// `stream_seed(seed, t)` and runs a contiguous slice of every round, so
+    /// output is reproducible for a given thread count but differs between
+    /// counts (one thread matches the sequential stream exactly).
+    pub threads: usize,
+    /// Drive episode `i` from `episode_seed(seed, i)` instead of a shared
+    /// stream. Output then no longer depends on the thread count.
+    pub independent_seeds: bool,
+    pub model: Model,
+    pub rng: RngKind,
+    pub options: AggregateOptions,
+    /// Stop early once the `options.ci_level` Wilson interval for
+    /// `objective_rate` has at most this half-width, checked every `ADAPTIVE_BLOCK` kept episodes;
+    /// `episodes` is then only the cap.
+    pub target_ci: Option<f64>,
+    /// Stop once this much wall time has passed since the run started,
+    /// checked every `TIME_CHECK_BLOCK` kept episodes; `episodes` is then
+    /// only the cap. The count reached depends on the machine.
+    pub time_budget: Option<Duration>,
+    /// Stop at the kept episode that brings the successes to this count
+    /// (inverse sampling); `episodes` is then only the cap.
+    pub until_successes: Option<usize>,
+    /// Run episodes in pairs `(2k, 2k + 1)` where the second replays the
+    /// first's uniforms as `1 - u`. Gaussian noise then uses the uncached
+    /// Box-Muller draw, so it no longer matches a plain run.
+    pub antithetic: bool,
+    /// Checked once per episode; when it reads true the run stops and the
+    /// aggregate over the episodes so far is marked `truncated`.
+    pub stop: Option<&'static AtomicBool>,
+    /// Range the parameters are clamped into wherever they are read or
+    /// searched (batch sets, sweep cells, optimizer candidates).
+    pub bounds: ParamBounds,
+    /// Start the single `Lcg` stream from this raw state instead of `seed`
+    /// (`--resume-rng`). Needs `RngKind::Lcg`, one thread and a shared stream.
+    pub rng_state: Option<u64>,
+    /// Receives that stream's final state once the run ends (`--dump-rng`).
+    pub checkpoint: Option<&'static AtomicU64>,
+    /// Keep only episodes with at least this stability. The rest are still
+    /// run, so the stream is unchanged, but only counted in `filtered_out`.
+    pub min_stability: Option<f64>,
+    /// Time every episode and record it in `Episode::compute_ns`. Reads the
+    /// clock twice per episode, so it costs throughput.
+    pub time_episodes: bool,
+}
+
+/// A worker's generator plus the raw draws of its last even-indexed episode,
+/// which the odd partner mirrors in antithetic mode.
+struct Worker {
+    rng: Generator,
+    draws: Vec<u64>,
+}
+
+/// Pair means of antithetic runs next to the per-episode spread.
+#[derive(Default)]
+struct PairStats {
+    /// The last kept even-indexed episode, with its index.
+    first: Option<(usize, Episode)>,
+    objective: Running,
+    elapsed: Running,
+    objective_pairs: Running,
+    elapsed_pairs: Running,
+}
+
+impl PairStats {
+    /// `index` is the global episode index; a pair counts only when both
+    /// halves were kept, so an odd episode pairs only with `index - 1`.
+    fn push(&mut self, index: usize, ep: &Episode) {
+        let hit = |e: &Episode| if e.objective_complete { 1.0 } else { 0.0 };
+        self.objective.push(hit(ep));
+        self.elapsed.push(widen(ep.elapsed_s));
+        if index.is_multiple_of(2) {
+            self.first = Some((index, *ep));
+        } else if let Some((_, first)) = self.first.take().filter(|(at, _)| at + 1 == index) {
+            self.objective_pairs.push((hit(&first) + hit(ep)) / 2.0);
+            self.elapsed_pairs
+                .push((widen(first.elapsed_s) + widen(ep.elapsed_s)) / 2.0);
+        }
+    }
+
+    /// `None` when no complete pair was kept, so there is no pair variance.
+    fn finish(&self) -> Option<VarianceReduction> {
+        if self.objective_pairs.count() == 0 {
+            return None;
+        }
+        let n = self.objective.count() as f64;
+        let pairs = self.objective_pairs.count() as f64;
+        Some(VarianceReduction {
+            pairs: self.objective_pairs.count() as usize,
+            objective_rate_var: self.objective_pairs.variance() / pairs,
+            objective_rate_naive_var: self.objective.variance() / n,
+            mean_elapsed_var: self.elapsed_pairs.variance() / pairs,
+            mean_elapsed_naive_var: self.elapsed.variance() / n,
+        })
+    }
+}
+
+impl RunConfig {
+    pub fn new(params: Params, seed: u64, episodes: usize) -> Self {
+        Self {
+            params,
+            seed,
+            episodes,
+            warmup: 0,
+            threads: 1,
+            independent_seeds: false,
+            model: Model::default(),
+            rng: RngKind::default(),
+            options: AggregateOptions::default(),
+            target_ci: None,
+            time_budget: None,
+            until_successes: None,
+            antithetic: false,
+            stop: None,
+            bounds: ParamBounds::UNIT,
+            rng_state: None,
+            checkpoint: None,
+            min_stability: None,
+            time_episodes: false,
+        }
+    }
+
+    /// 16 hex digits identifying everything that decides this run's output:
+    /// an FNV-1a hash of the crate version and the config as `SimConfig`
+    /// JSON plus any resume state, minus the settings that can't change the
+    /// output (the thread count under independent seeds, `bins` and
+    /// `mode_prominence` without a histogram). Keys are sorted and values
+    /// equal to `SimConfig::default()` left out, so identical inputs give
+    /// the same id, also after a new field with a default is added.
+    pub fn run_id(&self) -> String {
+        let threads = if self.independent_seeds {
+            1
+        } else {
+            self.threads
+        };
+        let defaults = AggregateOptions::default();
+        let options = if self.options.histogram.is_some() {
+            self.options
+        } else {
+            AggregateOptions {
+                bins: defaults.bins,
+                mode_prominence: defaults.mode_prominence,
+                ..self.options
+            }
+        };
+        let inputs = RunConfig {
+            threads,
+            options,
+            ..*self
+        };
+        let config = |config: &SimConfig| serde_json::to_value(config).expect("config serializes");
+        let mut tree = config(&SimConfig::from_run_config(&inputs));
+        without_defaults(&mut tree, &config(&SimConfig::default()));
+        if let (Some(state), Value::Object(map)) = (self.rng_state, &mut tree) {
+            map.insert("rng_state".to_string(), state.into());
+        }
+        let text = format!("{} {}", env!("CARGO_PKG_VERSION"), sorted_keys(tree));
+        format!("{:016x}", seed_from_str(&text))
+    }
+
+    /// Runs every episode, handing each to `on_episode` in index order, and
+    /// returns the streaming aggregate (no statistics that need retained rows).
+    pub fn stream<F: FnMut(&Episode)>(&self, on_episode: F) -> Aggregate {
+        self.stream_rows(false, on_episode).0
+    }
+
+    /// `stream`, also returning the kept rows when `keep` is set or
+    /// winsorizing needs them, and under `winsorize` their clamped copy,
+    /// which the sums were taken over. The one place rows are retained or
+    /// winsorized, so `run_with` reuses both instead of redoing either.
+    fn stream_rows<F: FnMut(&Episode)>(
+        &self,
+        keep: bool,
+        mut on_episode: F,
+    ) -> (Aggregate, Vec<Episode>, Option<Vec<Episode>>) {
+        let threads = self.threads.max(1);
+        let mut workers: Vec<Worker> = (0..threads)
+            .map(|t| Worker {
+                rng: match self.rng_state {
+                    Some(state) if t == 0 && self.rng == RngKind::Lcg => {
+                        Generator::Lcg(Lcg::from_state(state))
+                    }
+                    _ => self.rng.seeded(stream_seed(self.seed, t as u64)),
+                },
+                draws: Vec::new(),
+            })
+            .collect();
+        let mut pairs = PairStats::default();
+        let mut covariance = stats::RunningCovariance::<3>::default();
+        let mut acc = Accumulator::new();
+        // The bootstrap needs every outcome, even when rows are not retained.
+        let mut outcomes: Vec<bool> = Vec::new();
+        // Winsorizing needs every kept episode's percentiles before any sum.
+        let winsorize = self.options.winsorize > 0.0;
+        // With an early stop, `episodes` is only a cap and may be far too large.
+        let capacity = if !keep
+            || self.target_ci.is_some()
+            || self.time_budget.is_some()
+            || self.until_successes.is_some()
+        {
+            0
+        } else {
+            self.episodes
+        };
+        let mut rows: Vec<Episode> = Vec::with_capacity(capacity);
+        let mut seen = 0usize;
+        let mut kept = 0usize;
+        let mut successes = 0usize;
+        let z = stats::two_sided_z(self.options.ci_level);
+        let mut interrupted = false;
+        let mut filtered = 0usize;
+        // Only read the clock when asked: `Instant` panics on wasm32-unknown-unknown.
+        let started = self.time_budget.map(|_| Instant::now());
+        // Returns true once `target_ci` is met, `time_budget` is spent,
+        // `until_successes` is reached or `stop` is raised.
+        let mut visit = |ep: &Episode| {
+            if self.stop.is_some_and(|stop| stop.load(Ordering::Relaxed)) {
+                interrupted = true;
+                return true;
+            }
+            seen += 1;
+            if seen <= self.warmup {
+                return false;
+            }
+            if self
+                .min_stability
+                .is_some_and(|floor| widen(ep.stability) < floor)
+            {
+                filtered += 1;
+                return false;
+            }
+            if keep || winsorize {
+                rows.push(*ep);
+            }
+            if !winsorize {
+                acc.push(ep);
+            }
+            if self.antithetic {
+                pairs.push(seen - 1, ep);
+            }
+            if self.options.bootstrap > 0 {
+                outcomes.push(ep.objective_complete);
+            }
+            if self.options.covariance && !winsorize {
+                covariance.push(COVARIANCE_METRICS.map(|metric| metric.of(ep)));
+            }
+            on_episode(ep);
+            kept += 1;
+            successes += usize::from(ep.objective_complete);
+            let out_of_time = started
+                .zip(self.time_budget)
+                .is_some_and(|(started, budget)| {
+                    kept.is_multiple_of(TIME_CHECK_BLOCK) && started.elapsed() >= budget
+                });
+            let enough = self.until_successes.is_some_and(|n| successes >= n);
+            out_of_time
+                || enough
+                || match self.target_ci {
+                    Some(target) if kept.is_multiple_of(ADAPTIVE_BLOCK) => {
+                        let (lo, hi) = stats::wilson_interval(successes, kept, z);
+                        (hi - lo) / 2.0 <= target
+                    }
+                    _ => false,
+                }
+        };
+
+        let total = self.warmup + self.episodes;
+        if threads == 1 {
+            let mut chunk = [Episode::default(); EPISODE_CHUNK];
+            let mut done = 0usize;
+            'chunks: while done < total {
+                let len = (total - done).min(EPISODE_CHUNK);
+                self.fill(done, &mut workers[0], &mut chunk[..len]);
+                for ep in &chunk[..len] {
+                    if visit(ep) {
+                        break 'chunks;
+                    }
+                }
+                done += len;
+            }
+        } else {
+            let mut done = 0usize;
+            'rounds: while done < total {
+                let round = (total - done).min(BLOCK_PER_WORKER * threads);
+                for chunk in self.run_round(&mut workers, done, round) {
+                    for ep in &chunk {
+                        if visit(ep) {
+                            break 'rounds;
+                        }
+                    }
+                }
+                done += round;
+            }
+        }
+
+        if let (Some(checkpoint), Generator::Lcg(rng)) = (self.checkpoint, &workers[0].rng) {
+            checkpoint.store(rng.state(), Ordering::Relaxed);
+        }
+        let clamped = winsorize.then(|| winsorized(&rows, self.options.winsorize));
+        for ep in clamped.iter().flatten() {
+            acc.push(ep);
+            if self.options.covariance {
+                covariance.push(COVARIANCE_METRICS.map(|metric| metric.of(ep)));
+            }
+        }
+        let mut aggregate = acc.finish_with_ci(self.options.ci_level);
+        aggregate.truncated = interrupted;
+        aggregate.filtered_out = self.min_stability.map(|_| filtered);
+        if self.options.covariance {
+            aggregate.covariance = Some(stats::Covariance::new(&covariance));
+        }
+        if self.antithetic {
+            aggregate.antithetic = pairs.finish();
+        }
+        if let Some(target) = self.until_successes {
+            aggregate.inverse_sampling = Some(InverseSampling::new(target, successes, kept));
+        }
+        if self.options.bootstrap > 0 {
+            let mut rng = self.rng.seeded(stream_seed(self.seed, BOOTSTRAP_STREAM));
+            aggregate.add_bootstrap(&outcomes, self.options.bootstrap, &mut rng);
+        }
+        (aggregate, rows, clamped)
+    }
+
+    /// Runs every episode and returns the rows plus the full aggregate.
+    pub fn run(&self) -> (Vec<Episode>, Aggregate) {
+        self.run_with(|_| {})
+    }
+
+    /// `run`, also handing each episode to `on_episode` as it is kept.
+    pub fn run_with<F: FnMut(&Episode)>(&self, on_episode: F) -> (Vec<Episode>, Aggregate) {
+        let (mut aggregate, mut rows, clamped) = self.stream_rows(true, on_episode);
+        let summarized = clamped.as_deref().unwrap_or(&rows);
+        aggregate.add_row_stats(summarized, self.options.trim);
+        if let Some(metric) = self.options.histogram {
+            let prominence = self.options.mode_prominence;
+            aggregate.add_histogram(summarized, metric, self.options.bins, prominence);
+        }
+        drop(clamped);
+        aggregate.tag_outliers(&mut rows, self.options.outlier_k);
+        (rows, aggregate)
+    }
+
+    /// Reruns kept episode `index` alone, or `None` if a threaded run never
+    /// reaches it. With independent seeds it is reseeded directly; a shared
+    /// stream has to be replayed through every earlier episode (warmup
+    /// included), since their draw counts vary. Threads split each round by
+    /// `episodes`, so a threaded replay keeps the full count and picks the
+    /// episode out. `index` counts every episode after the warmup, whether
+    /// or not `min_stability` would keep it. `outlier` stays false: it is
+    /// relative to a whole run.
+    pub fn replay(&self, index: usize) -> Option<Episode> {
+        if self.independent_seeds {
+            let mut worker = Worker {
+                rng: self.rng.seeded(self.seed),
+                draws: Vec::new(),
+            };
+            return Some(self.episode(self.warmup + index, &mut worker));
+        }
+        let episodes = if self.threads > 1 {
+            self.episodes
+        } else {
+            index + 1
+        };
+        let prefix = RunConfig {
+            episodes,
+            min_stability: None,
+            target_ci: None,
+            time_budget: None,
+            until_successes: None,
+            stop: None,
+            checkpoint: None,
+            options: AggregateOptions::default(),
+            ..*self
+        };
+        let mut kept = 0usize;
+        let mut found = None;
+        prefix.stream(|ep| {
+            if kept == index {
+                found = Some(*ep);
+            }
+            kept += 1;
+        });
+        found
+    }
+
+    /// Episodes `first..first + out.len()` into `out`. A shared stream is
+    /// batched through `run_episodes_into` on the concrete generator, so
+    /// the loop has no per-draw dispatch; the other modes go per index.
+    fn fill(&self, first: usize, worker: &mut Worker, out: &mut [Episode]) {
+        if self.time_episodes {
+            for (index, slot) in (first..).zip(out) {
+                let started = Instant::now();
+                *slot = self.episode(index, worker);
+                slot.compute_ns = Some(started.elapsed().as_nanos() as u64);
+            }
+            return;
+        }
+        if !self.antithetic && !self.independent_seeds {
+            match &mut worker.rng {
+                Generator::Lcg(rng) => self.model.run_episodes_into(self.params, rng, out),
+                Generator::Pcg(rng) => self.model.run_episodes_into(self.params, rng, out),
+            }
+            return;
+        }
+        for (index, slot) in (first..).zip(out) {
+            *slot = self.episode(index, worker);
+        }
+    }
+
+    fn episode(&self, index: usize, worker: &mut Worker) -> Episode {
+        if !self.antithetic {
+            return if self.independent_seeds {
+                let mut own = self.rng.seeded(episode_seed(self.seed, index));
+                self.model.run_episode(self.params, &mut own)
+            } else {
+                self.model.run_episode(self.params, &mut worker.rng)
+            };
+        }
+        let first = index - index % 2;
+        if self.independent_seeds {
+            // Stateless: regenerate the even partner's draws from its seed.
+            let mut own = self.rng.seeded(episode_seed(self.seed, first));
+            let ep = self.model.run_episode(
+                self.params,
+                &mut Recording::new(&mut own, &mut worker.draws),
+            );
+            if index == first {
+                return ep;
+            }
+            let mut spare = self.rng.seeded(episode_seed(self.seed, index));
+            return self
+                .model
+                .run_episode(self.params, &mut Mirrored::new(&worker.draws, &mut spare));
+        }
+        if index == first {
+            let mut recording = Recording::new(&mut worker.rng, &mut worker.draws);
+            self.model.run_episode(self.params, &mut recording)
+        } else {
+            let mut mirrored = Mirrored::new(&worker.draws, &mut worker.rng);
+            self.model.run_episode(self.params, &mut mirrored)
+        }
+    }
+
+    /// Splits the `round` episodes starting at index `first` into contiguous
+    /// per-worker chunks and runs them in parallel, returning them in order.
+    /// Antithetic chunks hold whole pairs so both halves share a worker.
+    fn run_round(&self, workers: &mut [Worker], first: usize, round: usize) -> Vec<Vec<Episode>> {
+        let count = workers.len();
+        let unit = if self.antithetic { 2 } else { 1 };
+        let units = round.div_ceil(unit);
+        thread::scope(|scope| {
+            let mut start = first;
+            let end = first + round;
+            let handles: Vec<_> = workers
+                .iter_mut()
+                .enumerate()
+                .map(|(t, worker)| {
+                    let len = (units / count + usize::from(t < units % count)) * unit;
+                    let indices = start..(start + len).min(end);
+                    start = indices.end;
+                    scope.spawn(move || {
+                        let mut chunk = vec![Episode::default(); indices.len()];
+                        self.fill(indices.start, worker, &mut chunk);
+                        chunk
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("episode worker panicked"))
+                .collect()
+        })
+    }
+}
+
+/// Drops every entry of `value` equal to the same path in `defaults`.
+fn without_defaults(value: &mut Value, defaults: &Value) {
+    if let (Value::Object(map), Value::Object(defaults)) = (value, defaults) {
+        map.retain(|key, item| match defaults.get(key) {
+            Some(default) if default == item => false,
+            Some(default) => {
+                without_defaults(item, default);
+                true
+            }
+            None => true,
+        });
+    }
+}
+
+/// `value` with every object's keys in sorted order, whatever the
+/// struct's field order.
+fn sorted_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            entries
+                .into_iter()
+                .map(|(key, item)| (key, sorted_keys(item)))
+                .collect()
+        }
+        Value::Array(items) => items.into_iter().map(sorted_keys).collect(),
+        other => other,
+    }
+}
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::run::stream_seed;
+use crate::{Lcg, Params};
+
+/// Stream id for the generator that proposes random candidates.
+const SAMPLE_STREAM: u64 = 0x5ea2c4;
+
+/// How candidate points in `[0,1]^4` are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sampler {
+    /// Independent uniforms from an `Lcg` seeded by the run seed.
+    #[default]
+    Random,
+    /// Halton sequence in bases 2, 3, 5, 7: evenly spread, deterministic,
+    /// and independent of the seed.
+    Halton,
+    /// Latin hypercube: each axis cut into `count` equal strata, every
+    /// stratum used once, with the pairing shuffled by a seeded `Lcg`.
+    Lhs,
+}
+
+impl Sampler {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(Self::Random),
+            "halton" => Some(Self::Halton),
+            "lhs" => Some(Self::Lhs),
+            _ => None,
+        }
+    }
+
+    /// Candidates for a search of `count` trials; only `Lhs` needs the
+    /// count up front, the other streams are endless.
+    pub fn points(self, seed: u64, count: usize) -> Points {
+        let rng = || Lcg::new(stream_seed(seed, SAMPLE_STREAM));
+        match self {
+            Self::Random => Points::Random(rng()),
+            Self::Halton => Points::Halton(1),
+            Self::Lhs => Points::Lhs(latin_hypercube(count, &mut rng()).into_iter()),
+        }
+    }
+}
+
+/// Candidate stream for a `Sampler`.
+pub enum Points {
+    Random(Lcg),
+    /// Next Halton index; index 0 (the origin) is skipped.
+    Halton(u64),
+    Lhs(std::vec::IntoIter<Params>),
+}
+
+impl Iterator for Points {
+    type Item = Params;
+
+    fn next(&mut self) -> Option<Params> {
+        if let Self::Lhs(points) = self {
+            return points.next();
+        }
+        Some(match self {
+            Self::Random(rng) => Params {
+                aggression: rng.next_f64(),
+                greed: rng.next_f64(),
+                safety: rng.next_f64(),
+                focus: rng.next_f64(),
+            },
+            Self::Halton(index) => {
+                let i = *index;
+                *index += 1;
+                Params {
+                    aggression: radical_inverse(i, 2),
+                    greed: radical_inverse(i, 3),
+                    safety: radical_inverse(i, 5),
+                    focus: radical_inverse(i, 7),
+                }
+            }
+            Self::Lhs(_) => unreachable!("handled above"),
+        })
+    }
+}
+
+/// Van der Corput radical inverse of `index` in `base`: its digits mirrored
+/// around the radix point.
+pub fn radical_inverse(mut index: u64, base: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut scale = inv_base;
+    let mut value = 0.0;
+    while index > 0 {
+        value += (index % base) as f64 * scale;
+        index /= base;
+        scale *= inv_base;
+    }
+    value
+}
+
+/// `count` points of `[0,1]^4` with exactly one in each of the `count`
+/// strata of every axis. Each axis gets its own Fisher-Yates shuffle of the
+/// strata, then a uniform jitter within each.
+pub fn latin_hypercube(count: usize, rng: &mut Lcg) -> Vec<Params> {
+    let mut axis = || {
+        let mut strata: Vec<usize> = (0..count).collect();
+        for i in (1..count).rev() {
+            // High bits: the raw LCG's low bits cycle with short periods.
+            let j = (rng.next_f64() * (i + 1) as f64) as usize;
+            strata.swap(i, j);
+        }
+        strata
+            .into_iter()
+            .map(|stratum| (stratum as f64 + rng.next_f64()) / count as f64)
+            .collect::<Vec<f64>>()
+    };
+    let (aggression, greed, safety, focus) = (axis(), axis(), axis(), axis());
+    (0..count)
+        .map(|i| Params {
+            aggression: aggression[i],
+            greed: greed[i],
+            safety: safety[i],
+            focus: focus[i],
+        })
+        .collect()
+}
@@ -0,0 +1,112 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use crate::stats::Running;
+use crate::{Aggregate, RunConfig};
+
+/// One rerun of a `--seeds` sweep.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SeedRun {
+    pub seed: u64,
+    pub aggregate: Aggregate,
+}
+
+/// Spread of `objective_rate` across the reruns: a direct measure of the
+/// Monte Carlo error of a single run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SeedVariance {
+    pub seeds: usize,
+    pub objective_rate_mean: f64,
+    /// Sample std (`n - 1`); `0.0` for a single seed.
+    pub objective_rate_std: f64,
+    pub objective_rates: Vec<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SeedSweep {
+    pub runs: Vec<SeedRun>,
+    pub seed_variance: SeedVariance,
+}
+
+/// Reruns `base` with seeds `base.seed, base.seed + 1, ...` (`count` of them,
+/// wrapping at `u64::MAX`).
+pub fn run_seeds(base: &RunConfig, count: usize) -> SeedSweep {
+    let seeds: Vec<u64> = (0..count as u64)
+        .map(|offset| base.seed.wrapping_add(offset))
+        .collect();
+    run_seed_list(base, &seeds)
+}
+
+/// Reruns `base` once per seed in `seeds`, in order. With `base.threads`
+/// above 1 the seeds are split into contiguous chunks, one per worker, and
+/// every rerun is single-threaded, so the runs match `threads: 1` exactly
+/// and the output does not depend on the thread count.
+pub fn run_seed_list(base: &RunConfig, seeds: &[u64]) -> SeedSweep {
+    let single = RunConfig {
+        threads: 1,
+        ..*base
+    };
+    let rerun = |seed: u64| SeedRun {
+        seed,
+        aggregate: RunConfig { seed, ..single }.run().1,
+    };
+    let workers = base.threads.clamp(1, seeds.len().max(1));
+    let runs: Vec<SeedRun> = if workers == 1 {
+        seeds.iter().map(|&seed| rerun(seed)).collect()
+    } else {
+        let chunk = seeds.len().div_ceil(workers);
+        thread::scope(|scope| {
+            let handles: Vec<_> = seeds
+                .chunks(chunk)
+                .map(|part| {
+                    scope.spawn(|| part.iter().map(|&seed| rerun(seed)).collect::<Vec<_>>())
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().expect("seed worker panicked"))
+                .collect()
+        })
+    };
+    let objective_rates: Vec<f64> = runs.iter().map(|r| r.aggregate.objective_rate).collect();
+    let mut rates = Running::default();
+    objective_rates.iter().for_each(|&rate| rates.push(rate));
+    SeedSweep {
+        seed_variance: SeedVariance {
+            seeds: runs.len(),
+            objective_rate_mean: rates.mean(),
+            objective_rate_std: rates.std(),
+            objective_rates,
+        },
+        runs,
+    }
+}
+
+pub fn load(path: &Path) -> Result<Vec<u64>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("cannot read seeds file {}: {}", path.display(), err))?;
+    parse(&text).map_err(|err| format!("invalid seeds file {}: {}", path.display(), err))
+}
+
+/// One `u64` per line; blank lines and `#` comments, whole-line or
+/// trailing, are skipped. At least one seed is required.
+pub fn parse(text: &str) -> Result<Vec<u64>, String> {
+    let mut seeds = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let seed = line
+            .parse()
+            .map_err(|_| format!("line {}: expected a u64 seed, got {:?}", number + 1, line))?;
+        seeds.push(seed);
+    }
+    if seeds.is_empty() {
+        return Err("no seeds".to_string());
+    }
+    Ok(seeds)
+}
@@ -0,0 +1,267 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::rng::RngKind;
+use crate::{AggregateOptions, Model, Noise, ParamBounds, Params, RunConfig, SimResult};
+
+/// Every option that decides a run's output, in one serializable value.
+/// Unlike `RunConfig` it carries no Ctrl-C or checkpoint hooks, so a whole
+/// run can be saved as JSON and rerun from it. Built with
+/// `SimConfig::new().episodes(1000).seed(7).params(p).build()`; fields
+/// left unset keep the CLI defaults.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimConfig {
+    pub params: Params,
+    pub seed: u64,
+    pub episodes: usize,
+    pub warmup: usize,
+    pub threads: usize,
+    pub independent_seeds: bool,
+    pub model: Model,
+    pub rng: RngKind,
+    pub options: AggregateOptions,
+    pub target_ci: Option<f64>,
+    pub time_budget_secs: Option<f64>,
+    pub until_successes: Option<usize>,
+    pub antithetic: bool,
+    pub bounds: ParamBounds,
+    pub min_stability: Option<f64>,
+    pub time_episodes: bool,
+}
+
+/// The CLI defaults: every parameter `0.5`, seed `1`, `10` episodes, and
+/// `RunConfig::new` for the rest.
+impl Default for SimConfig {
+    fn default() -> Self {
+        let params = Params {
+            aggression: 0.5,
+            greed: 0.5,
+            safety: 0.5,
+            focus: 0.5,
+        };
+        Self::from_run_config(&RunConfig::new(params, 1, 10))
+    }
+}
+
+impl SimConfig {
+    /// A builder starting from the defaults.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> SimConfigBuilder {
+        SimConfigBuilder {
+            config: Self::default(),
+        }
+    }
+
+    /// The options of `config`, without its hooks and resume state.
+    pub fn from_run_config(config: &RunConfig) -> Self {
+        Self {
+            params: config.params,
+            seed: config.seed,
+            episodes: config.episodes,
+            warmup: config.warmup,
+            threads: config.threads,
+            independent_seeds: config.independent_seeds,
+            model: config.model,
+            rng: config.rng,
+            options: config.options,
+            target_ci: config.target_ci,
+            time_budget_secs: config.time_budget.map(|budget| budget.as_secs_f64()),
+            until_successes: config.until_successes,
+            antithetic: config.antithetic,
+            bounds: config.bounds,
+            min_stability: config.min_stability,
+            time_episodes: config.time_episodes,
+        }
+    }
+
+    /// The `RunConfig` these options describe, with no hooks attached.
+    pub fn run_config(&self) -> RunConfig {
+        RunConfig {
+            warmup: self.warmup,
+            threads: self.threads,
+            independent_seeds: self.independent_seeds,
+            model: self.model,
+            rng: self.rng,
+            options: self.options,
+            target_ci: self.target_ci,
+            time_budget: self.time_budget_secs.map(Duration::from_secs_f64),
+            until_successes: self.until_successes,
+            antithetic: self.antithetic,
+            bounds: self.bounds,
+            min_stability: self.min_stability,
+            time_episodes: self.time_episodes,
+            ..RunConfig::new(self.params, self.seed, self.episodes)
+        }
+    }
+
+    /// Runs it and returns the rows plus the full aggregate, as `simulate` does.
+    pub fn run(&self) -> SimResult {
+        let (episodes, aggregate) = self.run_config().run();
+        SimResult {
+            episodes,
+            aggregate,
+        }
+    }
+
+    /// The checks `build` makes, for a config deserialized or edited by hand.
+    pub fn validate(&self) -> Result<(), String> {
+        ParamBounds::new(self.bounds.min, self.bounds.max)?;
+        let p = self.params;
+        for (name, value) in [
+            ("aggression", p.aggression),
+            ("greed", p.greed),
+            ("safety", p.safety),
+            ("focus", p.focus),
+        ] {
+            if !(self.bounds.min..=self.bounds.max).contains(&value) {
+                return Err(format!(
+                    "{} {} is outside the bounds [{}, {}]",
+                    name, value, self.bounds.min, self.bounds.max
+                ));
+            }
+        }
+        let options = self.options;
+        let checks = [
+            (self.episodes > 0, "episodes must be at least 1"),
+            (self.threads > 0, "threads must be at least 1"),
+            (options.bins > 0, "bins must be at least 1"),
+            (
+                options.ci_level > 0.0 && options.ci_level < 1.0,
+                "ci_level must be in (0, 1)",
+            ),
+            (
+                (0.0..0.5).contains(&options.trim),
+                "trim must be in [0, 0.5)",
+            ),
+            (
+                (0.0..0.5).contains(&options.winsorize),
+                "winsorize must be in [0, 0.5)",
+            ),
+            (
+                (0.0..=1.0).contains(&options.mode_prominence),
+                "mode_prominence must be in [0, 1]",
+            ),
+            (options.outlier_k > 0.0, "outlier_k must be positive"),
+            (
+                self.target_ci.is_none_or(|ci| ci > 0.0),
+                "target_ci must be positive",
+            ),
+            (
+                self.time_budget_secs
+                    .is_none_or(|secs| Duration::try_from_secs_f64(secs).is_ok()),
+                "time_budget_secs must be non-negative and fit in a Duration",
+            ),
+            (
+                self.until_successes.is_none_or(|n| n > 0),
+                "until_successes must be at least 1",
+            ),
+        ];
+        match checks.iter().find(|(ok, _)| !ok) {
+            Some((_, msg)) => Err(msg.to_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Sets `SimConfig` fields one call at a time; `build` checks them.
+#[derive(Clone, Copy, Debug)]
+pub struct SimConfigBuilder {
+    config: SimConfig,
+}
+
+impl SimConfigBuilder {
+    pub fn params(mut self, params: Params) -> Self {
+        self.config.params = params;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = seed;
+        self
+    }
+
+    /// Episodes to run, or the cap under an early stop.
+    pub fn episodes(mut self, episodes: usize) -> Self {
+        self.config.episodes = episodes;
+        self
+    }
+
+    pub fn warmup(mut self, warmup: usize) -> Self {
+        self.config.warmup = warmup;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.config.threads = threads;
+        self
+    }
+
+    pub fn independent_seeds(mut self, on: bool) -> Self {
+        self.config.independent_seeds = on;
+        self
+    }
+
+    pub fn model(mut self, model: Model) -> Self {
+        self.config.model = model;
+        self
+    }
+
+    /// Shorthand for the `noise` of the `model`.
+    pub fn noise(mut self, noise: Noise) -> Self {
+        self.config.model.noise = noise;
+        self
+    }
+
+    pub fn rng(mut self, rng: RngKind) -> Self {
+        self.config.rng = rng;
+        self
+    }
+
+    pub fn options(mut self, options: AggregateOptions) -> Self {
+        self.config.options = options;
+        self
+    }
+
+    pub fn target_ci(mut self, half_width: f64) -> Self {
+        self.config.target_ci = Some(half_width);
+        self
+    }
+
+    pub fn time_budget(mut self, budget: Duration) -> Self {
+        self.config.time_budget_secs = Some(budget.as_secs_f64());
+        self
+    }
+
+    pub fn until_successes(mut self, successes: usize) -> Self {
+        self.config.until_successes = Some(successes);
+        self
+    }
+
+    pub fn antithetic(mut self, on: bool) -> Self {
+        self.config.antithetic = on;
+        self
+    }
+
+    pub fn bounds(mut self, bounds: ParamBounds) -> Self {
+        self.config.bounds = bounds;
+        self
+    }
+
+    pub fn min_stability(mut self, floor: f64) -> Self {
+        self.config.min_stability = Some(floor);
+        self
+    }
+
+    pub fn time_episodes(mut self, on: bool) -> Self {
+        self.config.time_episodes = on;
+        self
+    }
+
+    /// The config, or the first problem `SimConfig::validate` finds.
+    pub fn build(self) -> Result<SimConfig, String> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
@@ -0,0 +1,808 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Metric, Rng};
+
+/// Linear-interpolated percentile (`p` in `0..=1`) of an ascending slice.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    assert!(!sorted.is_empty(), "percentile of an empty slice");
+    let rank = p * (sorted.len() - 1) as f64;
+    let lo = rank.floor() as usize;
+    let hi = rank.ceil() as usize;
+    let frac = rank - lo as f64;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * frac
+}
+
+pub fn median(sorted: &[f64]) -> f64 {
+    percentile(sorted, 0.5)
+}
+
+/// Interquartile range (75th minus 25th percentile) of an ascending slice.
+pub fn iqr(sorted: &[f64]) -> f64 {
+    percentile(sorted, 0.75) - percentile(sorted, 0.25)
+}
+
+/// Mean of an ascending slice after dropping `floor(n * trim)` values from
+/// each end (`trim` in `[0, 0.5)`); at least one value is always kept.
+pub fn trimmed_mean(sorted: &[f64], trim: f64) -> f64 {
+    assert!(!sorted.is_empty(), "trimmed mean of an empty slice");
+    let n = sorted.len();
+    let cut = ((n as f64 * trim) as usize).min((n - 1) / 2);
+    let kept = &sorted[cut..n - cut];
+    kept.iter().sum::<f64>() / kept.len() as f64
+}
+
+pub fn sorted_copy(values: impl Iterator<Item = f64>) -> Vec<f64> {
+    let mut out: Vec<f64> = values.collect();
+    out.sort_by(f64::total_cmp);
+    out
+}
+
+/// Reorders `values` so that `values[k]` is the one a full sort would put
+/// there, with nothing greater before it and nothing smaller after it
+/// (C++'s `nth_element`), and returns it. Quickselect with a median-of-three
+/// pivot and a three-way partition, so runs of equal values (e.g. clamped
+/// `elapsed_s`) stay linear: expected O(n), no allocation.
+pub fn nth_element(values: &mut [f64], k: usize) -> f64 {
+    assert!(k < values.len(), "nth_element past the end of the slice");
+    let (mut lo, mut hi) = (0, values.len());
+    while hi - lo > 1 {
+        let mut ends = [values[lo], values[lo + (hi - lo) / 2], values[hi - 1]];
+        ends.sort_by(f64::total_cmp);
+        let pivot = ends[1];
+        // [lo, lt) < pivot, [lt, i) == pivot, [gt, hi) > pivot.
+        let (mut lt, mut i, mut gt) = (lo, lo, hi);
+        while i < gt {
+            match values[i].total_cmp(&pivot) {
+                Ordering::Less => {
+                    values.swap(lt, i);
+                    lt += 1;
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    gt -= 1;
+                    values.swap(i, gt);
+                }
+                Ordering::Equal => i += 1,
+            }
+        }
+        if k < lt {
+            hi = lt;
+        } else if k >= gt {
+            lo = gt;
+        } else {
+            break;
+        }
+    }
+    values[k]
+}
+
+/// `percentile` without sorting: the same interpolated value, from one
+/// `nth_element` plus a scan for the next order statistic. Reorders `values`.
+pub fn select_percentile(values: &mut [f64], p: f64) -> f64 {
+    assert!(!values.is_empty(), "percentile of an empty slice");
+    let rank = p * (values.len() - 1) as f64;
+    let lo = rank.floor() as usize;
+    let hi = rank.ceil() as usize;
+    let frac = rank - lo as f64;
+    let below = nth_element(values, lo);
+    let above = if hi == lo {
+        below
+    } else {
+        values[lo + 1..]
+            .iter()
+            .copied()
+            .min_by(f64::total_cmp)
+            .expect("hi is in range")
+    };
+    below + (above - below) * frac
+}
+
+/// `trimmed_mean` from unsorted `values`. With nothing cut it is the plain
+/// mean, summed in the given order without touching `values`. Otherwise two
+/// `nth_element` calls move the `cut` smallest and largest values to the
+/// ends, and the kept middle is then sorted: quickselect leaves it in no
+/// particular order, and summing it ascending is what keeps the result
+/// bit-identical to `trimmed_mean`. Reorders `values` when it cuts.
+pub fn select_trimmed_mean(values: &mut [f64], trim: f64) -> f64 {
+    assert!(!values.is_empty(), "trimmed mean of an empty slice");
+    let n = values.len();
+    let cut = ((n as f64 * trim) as usize).min((n - 1) / 2);
+    if cut == 0 {
+        return values.iter().sum::<f64>() / n as f64;
+    }
+    nth_element(values, cut);
+    nth_element(&mut values[cut..], n - 2 * cut - 1);
+    let kept = &mut values[cut..n - cut];
+    kept.sort_by(f64::total_cmp);
+    kept.iter().sum::<f64>() / kept.len() as f64
+}
+
+/// Smallest `|mean|` for which `Running::cv` reports a value.
+pub const CV_MIN_MEAN: f64 = 1e-9;
+
+/// Welford's online mean/variance accumulator, extended with the third and
+/// fourth central moments (Terriberry's update) and tracking the extremes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Running {
+    count: u64,
+    mean: f64,
+    m2: f64,
+    m3: f64,
+    m4: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Running {
+    pub fn push(&mut self, x: f64) {
+        if self.count == 0 {
+            self.min = x;
+            self.max = x;
+        } else {
+            self.min = self.min.min(x);
+            self.max = self.max.max(x);
+        }
+        self.count += 1;
+        let n = self.count as f64;
+        let delta = x - self.mean;
+        let delta_n = delta / n;
+        let term = delta * delta_n * (n - 1.0);
+        self.m4 += term * delta_n * delta_n * (n * n - 3.0 * n + 3.0)
+            + 6.0 * delta_n * delta_n * self.m2
+            - 4.0 * delta_n * self.m3;
+        self.m3 += term * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+        self.mean += delta / n;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// Rebuilds the state behind a summary: sample `variance`, moment
+    /// `skewness` and excess `kurtosis` (`None` reads as zero m3/m4, right
+    /// when there is no spread), and the extremes.
+    pub fn from_moments(
+        count: u64,
+        mean: f64,
+        variance: f64,
+        skewness: Option<f64>,
+        kurtosis: Option<f64>,
+        min: Option<f64>,
+        max: Option<f64>,
+    ) -> Self {
+        let n = count as f64;
+        let m2 = variance * count.saturating_sub(1) as f64;
+        Self {
+            count,
+            mean,
+            m2,
+            m3: skewness.map_or(0.0, |g1| g1 * m2 * m2.sqrt() / n.sqrt()),
+            m4: kurtosis.map_or(0.0, |g2| (g2 + 3.0) * m2 * m2 / n),
+            min: min.unwrap_or(0.0),
+            max: max.unwrap_or(0.0),
+        }
+    }
+
+    /// Folds `other` in as if its values had been pushed here (Chan et al.
+    /// for m2, Pébay for m3 and m4).
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let (na, nb) = (self.count as f64, other.count as f64);
+        let n = na + nb;
+        let d = other.mean - self.mean;
+        let (d2, ab) = (d * d, na * nb);
+        let m2 = self.m2 + other.m2 + d2 * ab / n;
+        let m3 = self.m3
+            + other.m3
+            + d * d2 * ab * (na - nb) / (n * n)
+            + 3.0 * d * (na * other.m2 - nb * self.m2) / n;
+        let m4 = self.m4
+            + other.m4
+            + d2 * d2 * ab * (na * na - ab + nb * nb) / (n * n * n)
+            + 6.0 * d2 * (na * na * other.m2 + nb * nb * self.m2) / (n * n)
+            + 4.0 * d * (na * other.m3 - nb * self.m3) / n;
+        *self = Self {
+            count: self.count + other.count,
+            mean: self.mean + d * nb / n,
+            m2,
+            m3,
+            m4,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        };
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Sample variance (`n - 1` denominator); `0.0` with fewer than two values.
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.m2 / (self.count - 1) as f64
+        }
+    }
+
+    pub fn std(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    /// Coefficient of variation `std / mean`; `None` when `|mean|` is below
+    /// `CV_MIN_MEAN`, where the ratio blows up.
+    pub fn cv(&self) -> Option<f64> {
+        (self.count > 0 && self.mean.abs() >= CV_MIN_MEAN).then(|| self.std() / self.mean)
+    }
+
+    /// Moment skewness `g1 = sqrt(n) m3 / m2^1.5`; `None` when every value is
+    /// equal (including fewer than two values), where it is undefined.
+    pub fn skewness(&self) -> Option<f64> {
+        (self.m2 > 0.0).then(|| (self.count as f64).sqrt() * self.m3 / (self.m2 * self.m2.sqrt()))
+    }
+
+    /// Excess kurtosis `g2 = n m4 / m2^2 - 3`; `None` like `skewness`.
+    pub fn excess_kurtosis(&self) -> Option<f64> {
+        (self.m2 > 0.0).then(|| self.count as f64 * self.m4 / (self.m2 * self.m2) - 3.0)
+    }
+
+    /// Smallest value pushed; `None` before the first.
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+}
+
+/// P² (Jain & Chlamtac) streaming estimate of the `p`-quantile: five
+/// markers whose heights are nudged with piecewise-parabolic steps, so the
+/// memory stays constant however many values are pushed.
+#[derive(Clone, Copy, Debug)]
+pub struct P2Quantile {
+    p: f64,
+    count: usize,
+    /// Marker heights; the first five values are buffered here unsorted.
+    heights: [f64; 5],
+    positions: [f64; 5],
+    desired: [f64; 5],
+    increments: [f64; 5],
+}
+
+impl P2Quantile {
+    pub fn new(p: f64) -> Self {
+        Self {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    pub fn push(&mut self, x: f64) {
+        if self.count < 5 {
+            self.heights[self.count] = x;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(f64::total_cmp);
+            }
+            return;
+        }
+        self.count += 1;
+        let q = &mut self.heights;
+        let k = if x < q[0] {
+            q[0] = x;
+            0
+        } else if x >= q[4] {
+            q[4] = x;
+            3
+        } else {
+            (0..4).rev().find(|&i| q[i] <= x).unwrap_or(0)
+        };
+        for n in &mut self.positions[k + 1..] {
+            *n += 1.0;
+        }
+        for (want, inc) in self.desired.iter_mut().zip(self.increments) {
+            *want += inc;
+        }
+        for i in 1..4 {
+            self.adjust(i);
+        }
+    }
+
+    fn adjust(&mut self, i: usize) {
+        let (q, n) = (&mut self.heights, &mut self.positions);
+        let d = self.desired[i] - n[i];
+        if !((d >= 1.0 && n[i + 1] - n[i] > 1.0) || (d <= -1.0 && n[i - 1] - n[i] < -1.0)) {
+            return;
+        }
+        let d = d.signum();
+        let parabolic = q[i]
+            + d / (n[i + 1] - n[i - 1])
+                * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                    + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]));
+        q[i] = if q[i - 1] < parabolic && parabolic < q[i + 1] {
+            parabolic
+        } else {
+            let j = if d > 0.0 { i + 1 } else { i - 1 };
+            q[i] + d * (q[j] - q[i]) / (n[j] - n[i])
+        };
+        n[i] += d;
+    }
+
+    /// Current estimate; exact (interpolated) while fewer than five values
+    /// have been seen, `None` before the first.
+    pub fn estimate(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            1..=4 => {
+                let mut seen = self.heights[..self.count].to_vec();
+                seen.sort_by(f64::total_cmp);
+                Some(percentile(&seen, self.p))
+            }
+            _ => Some(self.heights[2]),
+        }
+    }
+}
+
+/// Two-sided 95% standard normal quantile.
+pub const Z_95: f64 = 1.959_963_984_540_054;
+
+/// Inverse standard normal CDF for `p` in `(0, 1)`, by Acklam's rational
+/// approximation (relative error below 1.2e-9).
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const LOW: f64 = 0.024_25;
+    let horner = |coeffs: &[f64], x: f64| coeffs.iter().fold(0.0, |acc, c| acc * x + c);
+    let tail = |q: f64| horner(&C, q) / (horner(&D, q) * q + 1.0);
+    if p < LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        horner(&A, r) * q / (horner(&B, r) * r + 1.0)
+    }
+}
+
+/// Standard normal quantile for a two-sided interval of coverage `level`.
+pub fn two_sided_z(level: f64) -> f64 {
+    normal_quantile(0.5 + level / 2.0)
+}
+
+/// Wilson score interval for `successes` out of `n` Bernoulli trials.
+pub fn wilson_interval(successes: usize, n: usize, z: f64) -> (f64, f64) {
+    if n == 0 {
+        return (0.0, 1.0);
+    }
+    let n = n as f64;
+    let p = successes as f64 / n;
+    let z2 = z * z;
+    let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let half = z / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    ((center - half).max(0.0), (center + half).min(1.0))
+}
+
+/// Standard normal CDF, via the Chebyshev fit to `erfc` from Numerical
+/// Recipes (fractional error below 1.2e-7).
+pub fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let upper = 0.5 * t * poly.exp();
+    if x >= 0.0 {
+        1.0 - upper
+    } else {
+        upper
+    }
+}
+
+/// Two-sided p-value of Student's t statistic `t` with `df` (possibly
+/// fractional) degrees of freedom.
+pub fn student_t_p_value(t: f64, df: f64) -> f64 {
+    incomplete_beta(df / 2.0, 0.5, df / (df + t * t))
+}
+
+/// Lanczos approximation (g = 7) of `ln Γ(x)` for `x > 0`.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFS[0], |acc, (i, c)| acc + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+/// Regularized incomplete beta `I_x(a, b)`, by Lentz's continued fraction.
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..=300 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        for num in [
+            m * (b - m) * x / ((a + m2 - 1.0) * (a + m2)),
+            -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0)),
+        ] {
+            d = 1.0 + num * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + num / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-14 {
+            break;
+        }
+    }
+    h
+}
+
+/// Welford-style co-moment accumulator for the Pearson correlation of pairs.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RunningCorr {
+    count: u64,
+    mean_x: f64,
+    mean_y: f64,
+    m2_x: f64,
+    m2_y: f64,
+    c_xy: f64,
+}
+
+impl RunningCorr {
+    pub fn push(&mut self, x: f64, y: f64) {
+        self.count += 1;
+        let n = self.count as f64;
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+        self.mean_x += dx / n;
+        self.mean_y += dy / n;
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.c_xy += dx * (y - self.mean_y);
+    }
+
+    /// Rebuilds the co-moments from sample variances and the correlation;
+    /// `None` reads as zero covariance, right when either has no spread.
+    pub fn from_moments(
+        count: u64,
+        (mean_x, var_x): (f64, f64),
+        (mean_y, var_y): (f64, f64),
+        correlation: Option<f64>,
+    ) -> Self {
+        let dof = count.saturating_sub(1) as f64;
+        let (m2_x, m2_y) = (var_x * dof, var_y * dof);
+        Self {
+            count,
+            mean_x,
+            mean_y,
+            m2_x,
+            m2_y,
+            c_xy: correlation.map_or(0.0, |r| r * (m2_x * m2_y).sqrt()),
+        }
+    }
+
+    /// Folds `other` in as if its pairs had been pushed here.
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let (na, nb) = (self.count as f64, other.count as f64);
+        let n = na + nb;
+        let (dx, dy) = (other.mean_x - self.mean_x, other.mean_y - self.mean_y);
+        let ab = na * nb / n;
+        *self = Self {
+            count: self.count + other.count,
+            mean_x: self.mean_x + dx * nb / n,
+            mean_y: self.mean_y + dy * nb / n,
+            m2_x: self.m2_x + other.m2_x + dx * dx * ab,
+            m2_y: self.m2_y + other.m2_y + dy * dy * ab,
+            c_xy: self.c_xy + other.c_xy + dx * dy * ab,
+        };
+    }
+
+    /// Pearson coefficient; `None` when either series has zero variance
+    /// (including fewer than two pairs).
+    pub fn correlation(&self) -> Option<f64> {
+        if self.m2_x <= 0.0 || self.m2_y <= 0.0 {
+            return None;
+        }
+        Some((self.c_xy / (self.m2_x * self.m2_y).sqrt()).clamp(-1.0, 1.0))
+    }
+}
+
+/// Welford co-moment accumulator for the covariance of `N` metrics at once:
+/// each push updates the means and every pairwise co-moment in one pass.
+#[derive(Clone, Copy, Debug)]
+pub struct RunningCovariance<const N: usize> {
+    count: u64,
+    mean: [f64; N],
+    comoment: [[f64; N]; N],
+}
+
+impl<const N: usize> Default for RunningCovariance<N> {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            mean: [0.0; N],
+            comoment: [[0.0; N]; N],
+        }
+    }
+}
+
+impl<const N: usize> RunningCovariance<N> {
+    pub fn push(&mut self, x: [f64; N]) {
+        self.count += 1;
+        let n = self.count as f64;
+        let before: [f64; N] = std::array::from_fn(|i| x[i] - self.mean[i]);
+        for (mean, d) in self.mean.iter_mut().zip(before) {
+            *mean += d / n;
+        }
+        // Only the upper triangle is updated and mirrored, so the matrix
+        // stays exactly symmetric despite rounding.
+        let after: [f64; N] = std::array::from_fn(|j| x[j] - self.mean[j]);
+        for (i, &d) in before.iter().enumerate() {
+            for (j, &e) in after.iter().enumerate().skip(i) {
+                let c = self.comoment[i][j] + d * e;
+                self.comoment[i][j] = c;
+                self.comoment[j][i] = c;
+            }
+        }
+    }
+
+    /// Sample covariance matrix (`n - 1`); all zeros below two samples.
+    pub fn covariance(&self) -> [[f64; N]; N] {
+        if self.count < 2 {
+            return [[0.0; N]; N];
+        }
+        let dof = (self.count - 1) as f64;
+        self.comoment.map(|row| row.map(|c| c / dof))
+    }
+
+    /// Pearson correlation matrix; entries involving a metric with no
+    /// spread, or every entry below two samples, are zero.
+    pub fn correlation(&self) -> [[f64; N]; N] {
+        let c = &self.comoment;
+        std::array::from_fn(|i| {
+            std::array::from_fn(|j| {
+                if c[i][i] <= 0.0 || c[j][j] <= 0.0 {
+                    0.0
+                } else {
+                    (c[i][j] / (c[i][i] * c[j][j]).sqrt()).clamp(-1.0, 1.0)
+                }
+            })
+        })
+    }
+}
+
+/// Metrics of `Covariance`, in matrix order.
+pub const COVARIANCE_METRICS: [Metric; 3] =
+    [Metric::UnlockRate, Metric::Stability, Metric::Elapsed];
+
+/// Symmetric covariance and correlation matrices of `metrics`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Covariance {
+    pub metrics: [Metric; 3],
+    pub covariance: [[f64; 3]; 3],
+    pub correlation: [[f64; 3]; 3],
+}
+
+impl Covariance {
+    pub fn new(acc: &RunningCovariance<3>) -> Self {
+        Self {
+            metrics: COVARIANCE_METRICS,
+            covariance: acc.covariance(),
+            correlation: acc.correlation(),
+        }
+    }
+}
+
+/// Percentile bootstrap of a Bernoulli rate: resamples `outcomes` with
+/// replacement `resamples` times and returns the 2.5th/97.5th percentiles.
+pub fn bootstrap_rate<R: Rng>(outcomes: &[bool], resamples: usize, rng: &mut R) -> (f64, f64) {
+    let n = outcomes.len();
+    let mut rates = Vec::with_capacity(resamples);
+    for _ in 0..resamples {
+        let mut hits = 0usize;
+        for _ in 0..n {
+            let idx = ((rng.next_f64() * n as f64) as usize).min(n - 1);
+            if outcomes[idx] {
+                hits += 1;
+            }
+        }
+        rates.push(hits as f64 / n as f64);
+    }
+    rates.sort_by(f64::total_cmp);
+    (percentile(&rates, 0.025), percentile(&rates, 0.975))
+}
+
+/// Equal-width histogram of one metric; `edges` has one more entry than `counts`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    pub metric: Metric,
+    pub edges: Vec<f64>,
+    pub counts: Vec<usize>,
+    /// Peaks found by `modes`; empty until it runs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modes: Vec<Mode>,
+}
+
+/// A peak of a histogram: a run of equal bins with lower ones (or the ends)
+/// on both sides.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Mode {
+    /// Midpoint of the peak's bins.
+    pub center: f64,
+    pub count: usize,
+    /// Height above the higher of the two lowest bins separating the peak
+    /// from a taller bin on each side, as a fraction of the tallest bin;
+    /// outside its range the histogram counts as empty, so the tallest
+    /// peak has prominence `1`.
+    pub prominence: f64,
+}
+
+/// Lowest count walking away from a peak of height `count` until a taller
+/// bin, or `0` if the walk runs off the end.
+fn col(count: usize, side: impl Iterator<Item = usize>) -> usize {
+    let mut low = count;
+    for c in side {
+        if c > count {
+            return low;
+        }
+        low = low.min(c);
+    }
+    0
+}
+
+/// The peaks of `histogram` with prominence at least `min_prominence`,
+/// left to right. A single regime gives one; more suggest distinct ones.
+pub fn modes(histogram: &Histogram, min_prominence: f64) -> Vec<Mode> {
+    let counts = &histogram.counts;
+    let tallest = counts.iter().copied().max().unwrap_or(0);
+    let mut modes = Vec::new();
+    let mut start = 0;
+    while start < counts.len() {
+        let count = counts[start];
+        let mut end = start;
+        while end + 1 < counts.len() && counts[end + 1] == count {
+            end += 1;
+        }
+        let rises = start == 0 || counts[start - 1] < count;
+        let falls = end + 1 == counts.len() || counts[end + 1] < count;
+        if count > 0 && rises && falls {
+            let left = col(count, counts[..start].iter().rev().copied());
+            let right = col(count, counts[end + 1..].iter().copied());
+            let prominence = (count - left.max(right)) as f64 / tallest as f64;
+            if prominence >= min_prominence {
+                modes.push(Mode {
+                    center: (histogram.edges[start] + histogram.edges[end + 1]) / 2.0,
+                    count,
+                    prominence,
+                });
+            }
+        }
+        start = end + 1;
+    }
+    modes
+}
+
+/// Bins `values` into `bins` equal buckets spanning their min and max; the
+/// last bucket includes the max. Returns `None` for no values.
+pub fn histogram(metric: Metric, values: &[f64], bins: usize) -> Option<Histogram> {
+    let bins = bins.max(1);
+    let lo = values.iter().copied().reduce(f64::min)?;
+    let hi = values.iter().copied().reduce(f64::max)?;
+    let width = (hi - lo) / bins as f64;
+    let edges = (0..=bins)
+        .map(|i| if i == bins { hi } else { lo + i as f64 * width })
+        .collect();
+    let mut counts = vec![0usize; bins];
+    for &v in values {
+        let idx = if width > 0.0 {
+            (((v - lo) / width) as usize).min(bins - 1)
+        } else {
+            0
+        };
+        counts[idx] += 1;
+    }
+    Some(Histogram {
+        metric,
+        edges,
+        counts,
+        modes: Vec::new(),
+    })
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Aggregate, ParamBounds, Params};
+
+/// Grids larger than this get a warning before they run.
+pub const LARGE_GRID_CELLS: usize = 100_000;
+
+/// Inclusive `min:max:step` range for one parameter axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParamRange {
+    pub min: f64,
+    pub max: f64,
+    pub step: f64,
+}
+
+impl ParamRange {
+    pub fn fixed(value: f64) -> Self {
+        Self {
+            min: value,
+            max: value,
+            step: 1.0,
+        }
+    }
+
+    /// Parses `min:max:step`, or a bare value for a single-point axis.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = text.split(':').collect();
+        let num = |s: &str| {
+            s.trim()
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number {:?} in range {:?}", s, text))
+        };
+        let range = match parts.as_slice() {
+            [value] => Self::fixed(num(value)?),
+            [min, max, step] => Self {
+                min: num(min)?,
+                max: num(max)?,
+                step: num(step)?,
+            },
+            _ => return Err(format!("range {:?} is not min:max:step", text)),
+        };
+        if !(range.min.is_finite() && range.max.is_finite()) {
+            return Err(format!("range {:?} needs finite bounds", text));
+        }
+        if !range.step.is_finite() || range.step <= 0.0 {
+            return Err(format!("range {:?} needs a positive step", text));
+        }
+        if range.min > range.max {
+            return Err(format!("range {:?} is empty (min > max)", text));
+        }
+        Ok(range)
+    }
+
+    /// Number of grid points `min, min + step, ...` up to and including
+    /// `max`; an error when it doesn't fit in a `usize`.
+    pub fn count(&self) -> Result<usize, String> {
+        let steps = ((self.max - self.min) / self.step + 1e-9).floor();
+        if steps >= usize::MAX as f64 {
+            return Err(format!(
+                "range {:?}:{:?}:{:?} has too many points",
+                self.min, self.max, self.step
+            ));
+        }
+        Ok(steps as usize + 1)
+    }
+
+    /// The `index`-th grid point, `min + index * step`.
+    pub fn value(&self, index: usize) -> f64 {
+        self.min + index as f64 * self.step
+    }
+}
+
+/// Cartesian product of the four parameter axes, in
+/// aggression/greed/safety/focus order (focus varies fastest).
+/// Points are computed as they are visited, so no axis is materialized.
+#[derive(Clone, Debug)]
+pub struct Grid {
+    ranges: [ParamRange; 4],
+    counts: [usize; 4],
+    cells: usize,
+    bounds: ParamBounds,
+}
+
+impl Grid {
+    pub fn new(ranges: [ParamRange; 4]) -> Result<Self, String> {
+        Self::with_bounds(ranges, ParamBounds::UNIT)
+    }
+
+    /// Cells are clamped into `bounds` instead of `[0, 1]`. Fails when an
+    /// axis or the cell count overflows a `usize`.
+    pub fn with_bounds(ranges: [ParamRange; 4], bounds: ParamBounds) -> Result<Self, String> {
+        let mut counts = [0; 4];
+        let mut cells = 1usize;
+        for (count, range) in counts.iter_mut().zip(&ranges) {
+            *count = range.count()?;
+            cells = cells
+                .checked_mul(*count)
+                .ok_or("sweep grid has too many cells")?;
+        }
+        Ok(Self {
+            ranges,
+            counts,
+            cells,
+            bounds,
+        })
+    }
+
+    pub fn cells(&self) -> usize {
+        self.cells
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Params> + '_ {
+        let [a, g, s, f] = self.ranges;
+        let [na, ng, ns, nf] = self.counts;
+        let bounds = self.bounds;
+        (0..na)
+            .map(move |i| a.value(i))
+            .flat_map(move |aggression| {
+                (0..ng).map(move |i| g.value(i)).flat_map(move |greed| {
+                    (0..ns).map(move |i| s.value(i)).flat_map(move |safety| {
+                        (0..nf).map(move |i| f.value(i)).map(move |focus| {
+                            Params {
+                                aggression,
+                                greed,
+                                safety,
+                                focus,
+                            }
+                            .clamped_to(bounds)
+                        })
+                    })
+                })
+            })
+    }
+}
+
+/// One line of sweep output.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SweepRow {
+    pub params: Params,
+    pub aggregate: Aggregate,
+}
@@ -0,0 +1,633 @@
+use std::io::Read;
+use std::process::{Command, Output};
+
+fn sim_core(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_sim-core"))
+        .args(args)
+        .output()
+        .expect("run sim-core")
+}
+
+#[test]
+fn equals_and_space_forms_agree() {
+    let spaced = sim_core(&["--episodes", "3", "--seed", "9", "--aggression", "0.7"]);
+    let joined = sim_core(&["--episodes=3", "--seed=9", "--aggression=0.7"]);
+    assert!(spaced.status.success());
+    assert!(joined.status.success());
+    assert_eq!(spaced.stdout, joined.stdout);
+}
+
+#[test]
+fn short_parameter_flags_match_the_long_ones() {
+    let run = |flags: &str| {
+        let args: Vec<&str> = "-n 3 --seed 9"
+            .split_whitespace()
+            .chain(flags.split_whitespace())
+            .collect();
+        sim_core(&args)
+    };
+    let long = run("--aggression 0.7 --greed 0.2 --safety 0.3 --focus 0.9");
+    let short = run("-a 0.7 -g 0.2 -s 0.3 -f 0.9");
+    assert!(long.status.success());
+    assert_eq!(long.stdout, short.stdout);
+}
+
+#[test]
+fn empty_equals_value_is_rejected() {
+    let output = sim_core(&["--episodes="]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--episodes"), "{}", stderr);
+}
+
+#[test]
+fn meta_records_seed_and_params() {
+    let json = sim_core(&["--episodes", "2", "--seed", "5", "--greed", "0.25"]);
+    let run: serde_json::Value = serde_json::from_slice(&json.stdout).unwrap();
+    assert_eq!(run["meta"]["seed"], 5);
+    assert_eq!(run["meta"]["greed"], 0.25);
+    assert_eq!(run["meta"]["episodes"], 2);
+    assert_eq!(run["meta"]["version"], env!("CARGO_PKG_VERSION"));
+
+    let ndjson = sim_core(&[
+        "--episodes",
+        "2",
+        "--seed",
+        "5",
+        "--greed",
+        "0.25",
+        "--format",
+        "ndjson",
+    ]);
+    let first = String::from_utf8(ndjson.stdout).unwrap();
+    let first: serde_json::Value = serde_json::from_str(first.lines().next().unwrap()).unwrap();
+    assert_eq!(first["meta"], run["meta"]);
+}
+
+#[test]
+fn fail_under_sets_exit_status() {
+    let below = sim_core(&["--episodes", "50", "--fail-under", "0.99"]);
+    assert_eq!(below.status.code(), Some(2));
+    assert!(!below.stdout.is_empty(), "output is still written");
+    let above = sim_core(&["--episodes", "50", "--fail-under", "0.01"]);
+    assert_eq!(above.status.code(), Some(0));
+}
+
+#[test]
+fn unreadable_config_exits_1() {
+    let output = sim_core(&["--config", "/nonexistent/sim.toml"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.starts_with("error: cannot read config"),
+        "{}",
+        stderr
+    );
+}
+
+fn sim_core_with_stdin(args: &[&str], input: &str) -> Output {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sim-core"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn sim-core");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().expect("wait for sim-core")
+}
+
+#[test]
+fn params_stdin_matches_flags() {
+    let piped = sim_core_with_stdin(
+        &["--episodes", "4", "--params-stdin"],
+        r#"{"aggression": 0.7, "greed": 0.5, "safety": 1.5, "focus": 0.5}"#,
+    );
+    let flags = sim_core(&["--episodes", "4", "--aggression", "0.7", "--safety", "1"]);
+    assert!(piped.status.success());
+    assert_eq!(piped.stdout, flags.stdout);
+}
+
+#[test]
+fn params_stdin_rejects_empty_and_malformed_input() {
+    for input in ["", "{\"aggression\": 0.7", "{\"aggression\": 0.7}"] {
+        let output = sim_core_with_stdin(&["--params-stdin"], input);
+        assert_eq!(output.status.code(), Some(1), "{:?}", input);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("invalid params on stdin"), "{}", stderr);
+    }
+}
+
+#[test]
+fn preset_is_overridden_by_flags() {
+    let preset = sim_core(&[
+        "--episodes",
+        "4",
+        "--preset",
+        "aggressive",
+        "--safety",
+        "0.4",
+    ]);
+    let flags = sim_core(&[
+        "--episodes",
+        "4",
+        "--aggression",
+        "0.9",
+        "--greed",
+        "0.8",
+        "--safety",
+        "0.4",
+        "--focus",
+        "0.6",
+    ]);
+    assert!(preset.status.success());
+    assert_eq!(preset.stdout, flags.stdout);
+    assert!(!sim_core(&["--preset", "reckless"]).status.success());
+}
+
+#[test]
+fn json_and_csv_out_come_from_one_run() {
+    let dir = std::env::temp_dir().join(format!("sim-core-multi-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (json, csv) = (dir.join("run.json"), dir.join("run.csv"));
+    let output = sim_core(&[
+        "--episodes",
+        "6",
+        "--seed",
+        "3",
+        "--json-out",
+        json.to_str().unwrap(),
+        "--csv-out",
+        csv.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let archived = std::fs::read(&json).unwrap();
+    assert_eq!(
+        archived,
+        sim_core(&["--episodes", "6", "--seed", "3"]).stdout
+    );
+    let rows = std::fs::read(&csv).unwrap();
+    let csv_run = sim_core(&["--episodes", "6", "--seed", "3", "--format", "csv"]);
+    assert_eq!(rows, csv_run.stdout);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+fn gunzip(bytes: &[u8]) -> Vec<u8> {
+    let mut plain = Vec::new();
+    flate2::read::GzDecoder::new(bytes)
+        .read_to_end(&mut plain)
+        .expect("complete gzip stream");
+    plain
+}
+
+#[test]
+fn gz_output_decompresses_to_plain_output() {
+    let dir = std::env::temp_dir().join(format!("sim-core-gzip-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("run.ndjson.gz");
+    let args = ["--episodes", "20", "--seed", "9", "--format", "ndjson"];
+    let output = sim_core(&[&args[..], &["--output", path.to_str().unwrap()]].concat());
+    assert!(output.status.success());
+    let plain = sim_core(&args).stdout;
+    assert_eq!(gunzip(&std::fs::read(&path).unwrap()), plain);
+
+    let piped = sim_core(&[&args[..], &["--gzip"]].concat());
+    assert!(piped.status.success());
+    assert_eq!(gunzip(&piped.stdout), plain);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn chart_goes_to_stderr_only() {
+    let plain = sim_core(&["--episodes", "40", "--seed", "2"]);
+    let charted = sim_core(&[
+        "--episodes",
+        "40",
+        "--seed",
+        "2",
+        "--chart",
+        "elapsed",
+        "--bins",
+        "5",
+    ]);
+    assert!(charted.status.success());
+    assert_eq!(charted.stdout, plain.stdout);
+    let stderr = String::from_utf8(charted.stderr).unwrap();
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert_eq!(lines[0], "elapsed: 40 episodes in 5 bins");
+    assert_eq!(lines.len(), 6);
+    assert!(lines[1..].iter().all(|line| line.contains(" | ")));
+    assert!(lines[1..].iter().any(|line| line.contains(&"#".repeat(50))));
+}
+
+#[test]
+fn quiet_and_verbose_only_change_stderr() {
+    let args = ["--episodes", "5", "--seed", "8", "--seed-str", "label"];
+    let normal = sim_core(&args);
+    assert!(String::from_utf8_lossy(&normal.stderr).starts_with("warning:"));
+
+    let quiet = sim_core(&[&args[..], &["--quiet"]].concat());
+    assert!(quiet.stderr.is_empty());
+    assert_eq!(quiet.stdout, normal.stdout);
+
+    let verbose = sim_core(&[&args[..], &["-v"]].concat());
+    let stderr = String::from_utf8_lossy(&verbose.stderr);
+    assert!(
+        stderr.contains("verbose: seed 8, 5 episodes, aggression 0.5"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("verbose: finished in "), "{}", stderr);
+    assert_eq!(verbose.stdout, normal.stdout);
+
+    assert!(!sim_core(&["--quiet", "--verbose"]).status.success());
+}
+
+#[test]
+fn param_bounds_widen_the_accepted_range() {
+    let outside = sim_core(&["--episodes", "2", "--aggression", "1.5"]);
+    assert_eq!(outside.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&outside.stderr).contains("within [0, 1]"));
+
+    let widened = sim_core(&[
+        "--episodes",
+        "2",
+        "--aggression",
+        "1.5",
+        "--safety",
+        "-0.5",
+        "--param-min",
+        "-1",
+        "--param-max",
+        "2",
+    ]);
+    assert!(widened.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&widened.stdout).unwrap();
+    assert_eq!(json["meta"]["aggression"], 1.5);
+    assert_eq!(json["meta"]["safety"], -0.5);
+
+    let inverted = sim_core(&["--param-min", "1", "--param-max", "0"]);
+    assert_eq!(inverted.status.code(), Some(2));
+}
+
+#[test]
+fn resumed_rng_continues_a_split_run() {
+    let dir = std::env::temp_dir().join(format!("sim-core-rng-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let state = dir.join("rng.txt");
+    let state = state.to_str().unwrap();
+    let csv = |args: &[&str]| {
+        let output = sim_core(&[&["--seed", "4", "--format", "csv"], args].concat());
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let full = csv(&["--episodes", "10"]);
+    csv(&["--episodes", "5", "--dump-rng", state]);
+    let resumed = csv(&["--episodes", "5", "--resume-rng", state]);
+    let tail: Vec<&str> = full.lines().skip(6).collect();
+    assert_eq!(resumed.lines().skip(1).collect::<Vec<_>>(), tail);
+
+    let threaded = sim_core(&["--threads", "2", "--dump-rng", state]);
+    assert_eq!(threaded.status.code(), Some(1));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn independent_seeds_output_ignores_thread_count() {
+    for format in ["json", "ndjson"] {
+        let args = [
+            "--episodes",
+            "5000",
+            "--seed",
+            "21",
+            "--independent-seeds",
+            "--format",
+            format,
+        ];
+        let single = sim_core(&[&args[..], &["--threads", "1"]].concat());
+        let threaded = sim_core(&[&args[..], &["--threads", "4"]].concat());
+        assert!(single.status.success() && threaded.status.success());
+        assert_eq!(single.stdout, threaded.stdout, "{}", format);
+    }
+}
+
+#[test]
+fn emit_every_streams_rolling_aggregates_then_the_final_one() {
+    let args = ["--episodes", "25", "--seed", "13"];
+    let output = sim_core(&[&args[..], &["--emit-every", "10"]].concat());
+    assert!(output.status.success());
+    let lines: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let counts: Vec<u64> = lines
+        .iter()
+        .map(|line| line["cumulative_episodes"].as_u64().unwrap())
+        .collect();
+    assert_eq!(counts, [10, 20, 25]);
+    assert_eq!(lines[1]["episodes"], 20);
+
+    let mut last = lines[2].clone();
+    last.as_object_mut().unwrap().remove("cumulative_episodes");
+    let summary = sim_core(&[&args[..], &["--summary-only"]].concat());
+    let summary: serde_json::Value = serde_json::from_slice(&summary.stdout).unwrap();
+    assert_eq!(last, summary);
+}
+
+#[test]
+fn simulate_matches_the_cli_run() {
+    use sim_core::output::to_json;
+    use sim_core::{simulate, Params};
+
+    let params = Params {
+        aggression: 0.7,
+        greed: 0.5,
+        safety: 0.5,
+        focus: 0.5,
+    };
+    let result = simulate(params, 5, 12);
+    let expected: serde_json::Value = serde_json::from_str(&to_json(&result).unwrap()).unwrap();
+    let output = sim_core(&["--episodes", "12", "--seed", "5", "--aggression", "0.7"]);
+    let mut run: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    run.as_object_mut().unwrap().remove("meta");
+    assert_eq!(run, expected);
+}
+
+#[test]
+fn precision_sets_json_and_csv_decimals() {
+    let args = ["--episodes", "4", "--seed", "3", "--precision", "2"];
+    let output = sim_core(&[&args[..], &["--summary-only"]].concat());
+    let aggregate: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let rate = aggregate["unlock_rate"].as_f64().unwrap();
+    assert_eq!(rate, (rate * 100.0).round() / 100.0);
+
+    let csv = sim_core(&[&args[..], &["--format", "csv"]].concat());
+    let csv = String::from_utf8(csv.stdout).unwrap();
+    let row = csv.lines().nth(1).unwrap();
+    let unlock = row.split(',').next().unwrap();
+    assert_eq!(unlock.split('.').nth(1).map(str::len), Some(2), "{}", row);
+
+    let rejected = sim_core(&["--episodes", "1", "--precision", "18"]);
+    assert_eq!(rejected.status.code(), Some(2));
+}
+
+#[test]
+fn aggregate_out_writes_the_final_aggregate_alone() {
+    let dir = std::env::temp_dir().join(format!("sim-core-aggregate-out-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("aggregate.json");
+    let path_arg = path.to_str().unwrap();
+    let args = ["--episodes", "6", "--seed", "8"];
+
+    let full = sim_core(&[&args[..], &["--aggregate-out", path_arg]].concat());
+    assert!(full.status.success());
+    let full: serde_json::Value = serde_json::from_slice(&full.stdout).unwrap();
+    let written: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(written, full["aggregate"]);
+
+    let summary = sim_core(&[&args[..], &["--summary-only", "--aggregate-out", path_arg]].concat());
+    assert_eq!(std::fs::read(&path).unwrap(), summary.stdout);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn time_unit_rescales_elapsed_fields_and_marks_meta() {
+    let args = ["--episodes", "5", "--seed", "21"];
+    let seconds = sim_core(&args);
+    let minutes = sim_core(&[&args[..], &["--time-unit", "m"]].concat());
+    let seconds: serde_json::Value = serde_json::from_slice(&seconds.stdout).unwrap();
+    let minutes: serde_json::Value = serde_json::from_slice(&minutes.stdout).unwrap();
+    assert!(seconds["meta"].get("time_unit").is_none());
+    assert_eq!(minutes["meta"]["time_unit"], "m");
+
+    let close = |s: &serde_json::Value, m: &serde_json::Value, factor: f64| {
+        let (s, m) = (s.as_f64().unwrap(), m.as_f64().unwrap());
+        assert!((s / factor - m).abs() < 1e-5, "{} s vs {} m", s, m);
+    };
+    close(
+        &seconds["episodes"][0]["elapsed_s"],
+        &minutes["episodes"][0]["elapsed_s"],
+        60.0,
+    );
+    for field in [
+        "mean_elapsed_s",
+        "elapsed_std",
+        "elapsed_max",
+        "elapsed_p90",
+    ] {
+        close(
+            &seconds["aggregate"][field],
+            &minutes["aggregate"][field],
+            60.0,
+        );
+    }
+    close(
+        &seconds["aggregate"]["elapsed_var"],
+        &minutes["aggregate"]["elapsed_var"],
+        3600.0,
+    );
+    assert_eq!(
+        seconds["aggregate"]["elapsed_cv"],
+        minutes["aggregate"]["elapsed_cv"]
+    );
+
+    let csv = sim_core(&[&args[..], &["--time-unit", "h", "--format", "csv"]].concat());
+    let csv = String::from_utf8(csv.stdout).unwrap();
+    assert!(
+        csv.starts_with("unlock_rate,objective_complete,stability,elapsed_h,"),
+        "{}",
+        csv
+    );
+}
+
+#[test]
+fn misspelled_flags_are_rejected_with_a_suggestion() {
+    for args in [
+        &["--agression", "0.9"][..],
+        &["run", "--agression", "0.9"],
+        &["sweep", "--agression", "0.9"],
+    ] {
+        let output = sim_core(args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(output.stdout.is_empty());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("'--agression'"), "{}", stderr);
+        assert!(stderr.contains("'--aggression'"), "{}", stderr);
+    }
+    let strict = sim_core(&["--episodes", "2", "--strict"]);
+    assert!(strict.status.success());
+    assert_eq!(strict.stdout, sim_core(&["--episodes", "2"]).stdout);
+}
+
+#[test]
+fn json_log_format_writes_one_object_per_stderr_line() {
+    let args = [
+        "--episodes",
+        "4",
+        "--seed",
+        "3",
+        "--seed-str",
+        "x",
+        "--verbose",
+    ];
+    let text = sim_core(&args);
+    let json = sim_core(&[&args[..], &["--log-format", "json"]].concat());
+    assert_eq!(text.stdout, json.stdout);
+    let text = String::from_utf8(text.stderr).unwrap();
+    assert!(
+        text.starts_with("warning: both --seed and --seed-str"),
+        "{}",
+        text
+    );
+
+    let lines: Vec<serde_json::Value> = String::from_utf8(json.stderr)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["level"], "warning");
+    assert!(lines[0].get("target").is_none());
+    assert_eq!(lines[1]["level"], "debug");
+    assert_eq!(lines[1]["episodes"], 4);
+    assert!(lines[2]["elapsed_s"].is_f64());
+
+    let failed = sim_core(&[
+        "--episodes",
+        "4",
+        "--fail-under",
+        "1",
+        "--log-format",
+        "json",
+    ]);
+    assert_eq!(failed.status.code(), Some(2));
+    let line: serde_json::Value = serde_json::from_slice(&failed.stderr).unwrap();
+    assert_eq!(
+        (&line["level"], &line["target"]),
+        (&"error".into(), &"fail".into())
+    );
+}
+
+#[test]
+fn meta_carries_a_deterministic_run_id_and_an_opt_in_timestamp() {
+    use sim_core::output::utc_timestamp;
+
+    let meta = |args: &[&str]| {
+        let output = sim_core(&[&["--episodes", "2"][..], args].concat());
+        let run: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        run["meta"].clone()
+    };
+    let first = meta(&["--seed", "4"]);
+    let id = first["run_id"].as_str().unwrap();
+    assert_eq!(id.len(), 16);
+    assert_eq!(meta(&["--seed", "4"]), first);
+    assert!(first.get("timestamp").is_none());
+    assert_ne!(meta(&["--seed", "5"])["run_id"], id);
+    assert_ne!(meta(&["--seed", "4", "--greed", "0.6"])["run_id"], id);
+
+    let stamped = meta(&["--seed", "4", "--timestamp"]);
+    assert_eq!(stamped["run_id"], id);
+    let stamp = stamped["timestamp"].as_str().unwrap();
+    assert!(stamp.len() == 20 && stamp.ends_with('Z'), "{}", stamp);
+
+    assert_eq!(utc_timestamp(0), "1970-01-01T00:00:00Z");
+    assert_eq!(utc_timestamp(951_825_600), "2000-02-29T12:00:00Z");
+    assert_eq!(utc_timestamp(4_102_444_799), "2099-12-31T23:59:59Z");
+}
+
+#[test]
+fn table_marks_the_fail_under_verdict_and_colours_only_when_asked() {
+    let args = ["--episodes", "50", "--seed", "6", "--table"];
+    let plain = sim_core(&args);
+    assert_eq!(plain.stdout, sim_core(&args[..4]).stdout);
+    let stderr = String::from_utf8(plain.stderr).unwrap();
+    assert!(stderr.starts_with("metric "), "{}", stderr);
+    assert!(stderr
+        .lines()
+        .any(|line| line.starts_with("objective_rate ")));
+    assert!(!stderr.contains('\x1b'), "piped stderr is not a terminal");
+
+    let failed = sim_core(&[&args[..], &["--fail-under", "0.99", "--color", "always"]].concat());
+    assert_eq!(failed.status.code(), Some(2));
+    let stderr = String::from_utf8(failed.stderr).unwrap();
+    assert!(
+        stderr.contains("\x1b[31mFAIL (< 0.99)\x1b[0m"),
+        "{}",
+        stderr
+    );
+
+    let passed = sim_core(&[&args[..], &["--fail-under", "0.01", "--color", "never"]].concat());
+    let stderr = String::from_utf8(passed.stderr).unwrap();
+    assert!(stderr.contains(" pass (>= 0.01)\n"), "{}", stderr);
+}
+
+#[test]
+fn unrepresentable_time_budgets_are_usage_errors() {
+    for secs in ["inf", "1e30", "nan", "0"] {
+        let output = sim_core(&["--time-budget-secs", secs]);
+        assert_eq!(output.status.code(), Some(2), "{}", secs);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+    }
+    let output = sim_core(&["--time-budget-secs", "1e9", "--max-episodes", "3"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn optimizer_steps_must_be_positive_and_finite() {
+    for method in ["hillclimb", "nelder-mead", "anneal"] {
+        for step in ["0", "-0.1", "nan", "inf"] {
+            let output = sim_core(&["optimize", "--method", method, "--step", step]);
+            assert_eq!(output.status.code(), Some(2), "{} {}", method, step);
+        }
+    }
+    let output = sim_core(&[
+        "optimize",
+        "-n",
+        "2",
+        "--method",
+        "hillclimb",
+        "--step",
+        "0.1",
+    ]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn noise_sigma_and_coefficients_must_be_finite_and_non_negative() {
+    for flag in [
+        "--noise-sigma",
+        "--coeff-unlock-greed",
+        "--coeff-elapsed-base",
+        "--unlock-noise",
+    ] {
+        for value in ["nan", "-0.1", "inf"] {
+            let output = sim_core(&["-n", "2", &format!("{}={}", flag, value)]);
+            assert_eq!(output.status.code(), Some(2), "{} {}", flag, value);
+        }
+    }
+    let output = sim_core(&["-n", "2", "--noise", "gaussian", "--noise-sigma", "0"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn sobol_needs_at_least_sixteen_samples() {
+    let output = sim_core(&["sobol", "-n", "1", "--samples", "15"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("at least 16"));
+    assert!(sim_core(&["sobol", "-n", "1", "--samples", "16"])
+        .status
+        .success());
+}
@@ -0,0 +1,295 @@
+use sim_core::{run_episode, run_episodes_into, Episode, FailReason, Lcg, Params, Rng};
+
+/// Replays a fixed list of uniforms so episode outputs can be checked exactly.
+struct Replay {
+    draws: Vec<f64>,
+    next: usize,
+}
+
+impl Replay {
+    fn new(draws: &[f64]) -> Self {
+        Self {
+            draws: draws.to_vec(),
+            next: 0,
+        }
+    }
+}
+
+impl Rng for Replay {
+    fn next_u64(&mut self) -> u64 {
+        (self.next_f64() * (1u64 << 53) as f64) as u64
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        let v = self.draws[self.next];
+        self.next += 1;
+        v
+    }
+}
+
+const BALANCED: Params = Params {
+    aggression: 0.5,
+    greed: 0.5,
+    safety: 0.5,
+    focus: 0.5,
+};
+
+#[test]
+fn midpoint_draws_give_noise_free_episode() {
+    let mut rng = Replay::new(&[0.5, 0.5, 0.0, 0.5]);
+    let ep = run_episode(BALANCED, &mut rng);
+
+    assert!((ep.unlock_rate - 0.49).abs() < 1e-12);
+    assert!((ep.stability - 0.42).abs() < 1e-12);
+    assert!(ep.objective_complete);
+    assert!((ep.elapsed_s - 1800.0 * (1.0 - 0.65 * 0.49) * 0.985).abs() < 1e-9);
+    assert_eq!(rng.next, 4, "an episode consumes exactly four uniforms");
+}
+
+#[test]
+fn high_objective_draw_fails_the_episode() {
+    let mut rng = Replay::new(&[0.5, 0.5, 0.999, 0.5]);
+    let ep = run_episode(BALANCED, &mut rng);
+    assert!(!ep.objective_complete);
+    // Even unlock_rate = 1 only lifts the success probability to ~0.86.
+    assert_eq!(ep.fail_reason, Some(FailReason::UnluckyDraw));
+}
+
+#[test]
+fn failure_blames_the_factor_that_would_have_saved_it() {
+    // p = 0.565; unlock_rate = 1 would give ~0.86, stability = 1 only ~0.70.
+    let mut rng = Replay::new(&[0.5, 0.5, 0.8, 0.5]);
+    assert_eq!(
+        run_episode(BALANCED, &mut rng).fail_reason,
+        Some(FailReason::LowUnlock)
+    );
+    let mut rng = Replay::new(&[0.5, 0.5, 0.0, 0.5]);
+    assert_eq!(run_episode(BALANCED, &mut rng).fail_reason, None);
+}
+
+/// Exposes only `next_u64`, so every other draw comes from the trait defaults.
+struct BitsOnly(Lcg);
+
+impl Rng for BitsOnly {
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+}
+
+#[test]
+fn trait_defaults_match_lcg_draws() {
+    let mut lcg = Lcg::new(7);
+    let mut bits = BitsOnly(Lcg::new(7));
+    for _ in 0..16 {
+        assert_eq!(
+            run_episode(BALANCED, &mut lcg),
+            run_episode(BALANCED, &mut bits)
+        );
+    }
+}
+
+#[test]
+fn batched_episodes_match_single_calls() {
+    let params = Params {
+        aggression: 0.7,
+        greed: 0.4,
+        safety: 0.6,
+        focus: 0.3,
+    };
+    let mut batched = [Episode::default(); 64];
+    run_episodes_into(params, &mut Lcg::new(11), &mut batched);
+    let mut rng = Lcg::new(11);
+    for ep in &batched {
+        assert_eq!(*ep, run_episode(params, &mut rng));
+    }
+}
+
+#[test]
+fn episode_iter_matches_eager_run() {
+    let params = Params {
+        aggression: 0.3,
+        greed: 0.8,
+        safety: 0.5,
+        focus: 0.6,
+    };
+    let (rows, _) = sim_core::run_batch(params, 21, 40);
+    let lazy: Vec<Episode> = sim_core::episodes(params, 21).take(40).collect();
+    assert_eq!(lazy, rows);
+    let first_fail = rows.iter().position(|ep| !ep.objective_complete).unwrap();
+    let streak = sim_core::episodes(params, 21)
+        .take_while(|ep| ep.objective_complete)
+        .count();
+    assert_eq!(streak, first_fail);
+}
+
+#[test]
+fn clamp_maps_nan_to_the_midpoint() {
+    use sim_core::clamp;
+
+    assert_eq!(clamp(f64::NAN, 0.0, 1.0), 0.5);
+    assert_eq!(clamp(f64::NAN, 80.0, 2000.0), 1040.0);
+    assert_eq!(clamp(-0.5, 0.0, 1.0), 0.0);
+    assert_eq!(clamp(f64::INFINITY, 0.0, 1.0), 1.0);
+    assert_eq!(clamp(0.25, 0.0, 1.0), 0.25);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "clamp bounds inverted")]
+fn clamp_rejects_inverted_bounds() {
+    sim_core::clamp(0.5, 1.0, 0.0);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "range bounds inverted")]
+fn range_rejects_inverted_bounds() {
+    Lcg::new(1).range(0.08, -0.08);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "range bounds inverted")]
+fn trait_range_rejects_inverted_bounds() {
+    Replay::new(&[0.5]).range(1.0, 0.0);
+}
+
+#[test]
+fn success_rules_share_the_stream_and_decide_differently() {
+    use sim_core::{Model, SuccessRule};
+
+    let rules = [
+        SuccessRule::Bernoulli,
+        SuccessRule::Threshold {
+            unlock: 0.48,
+            stability: 0.4,
+        },
+        SuccessRule::And {
+            unlock: 0.48,
+            stability: 0.4,
+        },
+    ];
+    let runs: Vec<Vec<Episode>> = rules
+        .iter()
+        .map(|&success| {
+            let model = Model {
+                success,
+                ..Model::default()
+            };
+            model.episodes(BALANCED, 11).take(200).collect()
+        })
+        .collect();
+    for ((bernoulli, threshold), and) in runs[0].iter().zip(&runs[1]).zip(&runs[2]) {
+        assert_eq!(threshold.elapsed_s, bernoulli.elapsed_s);
+        let passes = threshold.unlock_rate >= 0.48 && threshold.stability >= 0.4;
+        assert_eq!(threshold.objective_complete, passes);
+        assert_eq!(
+            and.objective_complete,
+            passes && bernoulli.objective_complete
+        );
+        if !passes {
+            let blamed = if threshold.unlock_rate < 0.48 {
+                FailReason::LowUnlock
+            } else {
+                FailReason::LowStability
+            };
+            assert_eq!(threshold.fail_reason, Some(blamed));
+            assert_eq!(and.fail_reason, Some(blamed));
+        }
+    }
+    assert!(runs[1].iter().any(|ep| ep.objective_complete));
+    assert!(runs[1].iter().any(|ep| !ep.objective_complete));
+}
+
+#[test]
+fn multistep_episodes_start_from_the_one_shot_state() {
+    use sim_core::Model;
+
+    let one_shot: Vec<Episode> = Model::default().episodes(BALANCED, 3).take(50).collect();
+    let explicit = Model {
+        steps: 1,
+        ..Model::default()
+    };
+    assert_eq!(
+        explicit.episodes(BALANCED, 3).take(50).collect::<Vec<_>>(),
+        one_shot
+    );
+    assert!(one_shot.iter().all(|ep| ep.trajectory.is_none()));
+
+    let model = Model {
+        steps: 4,
+        ..Model::default()
+    };
+    let mut rng = Lcg::new(3);
+    let first = run_episode(BALANCED, &mut Lcg::new(3));
+    let ep = model.run_episode(BALANCED, &mut rng);
+    let trajectory = ep.trajectory.expect("multi-step summary");
+    assert_eq!(trajectory.steps, 4);
+    assert_eq!(trajectory.unlock_first, first.unlock_rate);
+    assert!(trajectory.stability_min <= ep.stability);
+    assert!(ep.unlock_rate > first.unlock_rate);
+}
+
+#[test]
+fn generic_model_matches_in_f64_and_tracks_in_f32() {
+    use sim_core::{widen, Model};
+
+    let model = Model {
+        steps: 3,
+        ..Model::default()
+    };
+    let params = Params {
+        aggression: 0.7,
+        greed: 0.4,
+        safety: 0.6,
+        focus: 0.3,
+    };
+    let (mut a, mut b, mut c) = (Lcg::new(17), Lcg::new(17), Lcg::new(17));
+    for _ in 0..200 {
+        let plain = model.run_episode(params, &mut a);
+        assert_eq!(model.run_episode_as::<f64, _>(params, &mut b), plain);
+        let narrow = model.run_episode_as::<f32, _>(params, &mut c);
+        assert!(widen(narrow.unlock_rate - plain.unlock_rate).abs() < 1e-5);
+        assert!(widen(narrow.stability - plain.stability).abs() < 1e-5);
+        assert!(widen(narrow.elapsed_s - plain.elapsed_s).abs() < 1e-2);
+    }
+}
+
+#[test]
+fn noise_half_widths_come_from_the_coefficients() {
+    use sim_core::{widen, Coefficients, Model, ELAPSED_NOISE_CENTER};
+
+    // Lowest unlock, stability and elapsed noise; 0.5 for the objective draw.
+    let draws = [0.0, 0.0, 0.5, 0.0];
+    let default = Model::default().run_episode(BALANCED, &mut Replay::new(&draws));
+    let quiet = Model {
+        coefficients: Coefficients {
+            unlock_noise: 0.02,
+            stability_noise: 0.0,
+            elapsed_noise: 0.0,
+            ..Coefficients::DEFAULT
+        },
+        ..Model::default()
+    };
+    let ep = quiet.run_episode(BALANCED, &mut Replay::new(&draws));
+    // Tolerances an `f32` build's episode fields can meet too.
+    let unlock = widen(default.unlock_rate) + 0.06;
+    assert!((widen(ep.unlock_rate) - unlock).abs() < 1e-6);
+    assert!((widen(ep.stability) - (widen(default.stability) + 0.06)).abs() < 1e-6);
+    let elapsed = 1800.0 * (1.0 - 0.65 * widen(ep.unlock_rate)) * (1.0 + ELAPSED_NOISE_CENTER);
+    assert!((widen(ep.elapsed_s) - elapsed).abs() < 1e-3);
+
+    let still = Model {
+        coefficients: Coefficients {
+            unlock_noise: 0.0,
+            ..quiet.coefficients
+        },
+        ..quiet
+    };
+    let a = still.run_episode(BALANCED, &mut Lcg::new(1));
+    let b = still.run_episode(BALANCED, &mut Lcg::new(2));
+    assert_eq!(
+        (a.unlock_rate, a.stability, a.elapsed_s),
+        (b.unlock_rate, b.stability, b.elapsed_s)
+    );
+}
@@ -0,0 +1,162 @@
+use sim_core::rng::{seed_from_str, Generator};
+use sim_core::{Lcg, Pcg64, Rng, RngKind};
+
+fn mean_and_variance(rng: &mut impl Rng, draws: usize) -> (f64, f64) {
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    for _ in 0..draws {
+        let x = rng.next_f64();
+        sum += x;
+        sum_sq += x * x;
+    }
+    let mean = sum / draws as f64;
+    (mean, sum_sq / draws as f64 - mean * mean)
+}
+
+#[test]
+fn pcg_uniforms_have_unit_interval_moments() {
+    let (mean, variance) = mean_and_variance(&mut Pcg64::new(12345), 1_000_000);
+    assert!((mean - 0.5).abs() < 2e-3, "mean {}", mean);
+    assert!(
+        (variance - 1.0 / 12.0).abs() < 1e-3,
+        "variance {}",
+        variance
+    );
+}
+
+#[test]
+fn pcg_low_bits_are_balanced() {
+    let mut rng = Pcg64::new(1);
+    let ones: u32 = (0..100_000).map(|_| (rng.next_u64() & 1) as u32).sum();
+    assert!((ones as f64 / 100_000.0 - 0.5).abs() < 0.01);
+}
+
+#[test]
+fn pcg_is_reproducible_per_seed() {
+    let mut a = Pcg64::new(9);
+    let mut b = Pcg64::new(9);
+    let mut c = Pcg64::new(10);
+    let xs: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+    let ys: Vec<u64> = (0..8).map(|_| b.next_u64()).collect();
+    let zs: Vec<u64> = (0..8).map(|_| c.next_u64()).collect();
+    assert_eq!(xs, ys);
+    assert_ne!(xs, zs);
+}
+
+#[test]
+fn lcg_kind_matches_plain_lcg() {
+    let mut plain = Lcg::new(3);
+    let mut chosen = RngKind::Lcg.seeded(3);
+    assert!(matches!(chosen, Generator::Lcg(_)));
+    for _ in 0..8 {
+        assert_eq!(plain.next_u64(), chosen.next_u64());
+    }
+}
+
+#[test]
+fn seed_labels_hash_to_pinned_fnv1a_values() {
+    assert_eq!(seed_from_str(""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(seed_from_str("a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(seed_from_str("baseline-v3"), 5_243_755_017_662_094_886);
+}
+
+#[test]
+fn lcg_seed_one_first_values() {
+    let mut rng = Lcg::new(1);
+    let first: Vec<u64> = (0..4).map(|_| rng.next_u64()).collect();
+    assert_eq!(
+        first,
+        [
+            7806831264735756412,
+            9396908728118811419,
+            11960119808228829710,
+            7062582979898595269,
+        ]
+    );
+}
+
+#[test]
+fn lcg_jump_matches_stepping() {
+    for n in [0u64, 1, 2, 3, 7, 64, 1000, 12345] {
+        let mut stepped = Lcg::new(42);
+        for _ in 0..n {
+            stepped.next_u64();
+        }
+        let mut jumped = Lcg::new(42);
+        jumped.jump(n);
+        assert_eq!(jumped.next_u64(), stepped.next_u64(), "n = {}", n);
+    }
+}
+
+#[test]
+fn lcg_jumps_compose() {
+    let mut once = Lcg::new(9);
+    once.jump(1 << 40);
+    let mut twice = Lcg::new(9);
+    twice.jump(1 << 39);
+    twice.jump(1 << 39);
+    assert_eq!(once.next_u64(), twice.next_u64());
+}
+
+#[test]
+fn lcg_restored_state_continues_the_stream() {
+    let mut uninterrupted = Lcg::new(31);
+    let mut first = Lcg::new(31);
+    for _ in 0..100 {
+        assert_eq!(first.next_u64(), uninterrupted.next_u64());
+    }
+    let mut restored = Lcg::from_state(first.state());
+    for _ in 0..100 {
+        assert_eq!(restored.next_u64(), uninterrupted.next_u64());
+    }
+    assert_eq!(Lcg::from_state(0).state(), 0);
+}
+
+#[test]
+fn halton_radical_inverses() {
+    use sim_core::sample::radical_inverse;
+    assert_eq!(radical_inverse(1, 2), 0.5);
+    assert_eq!(radical_inverse(6, 2), 0.375);
+    assert!((radical_inverse(5, 3) - 7.0 / 9.0).abs() < 1e-15);
+}
+
+#[test]
+fn latin_hypercube_fills_every_stratum_once() {
+    use sim_core::sample::latin_hypercube;
+
+    let points = latin_hypercube(50, &mut Lcg::new(6));
+    assert_eq!(points.len(), 50);
+    let axes: [fn(&sim_core::Params) -> f64; 4] =
+        [|p| p.aggression, |p| p.greed, |p| p.safety, |p| p.focus];
+    for axis in axes {
+        let mut strata: Vec<usize> = points.iter().map(|p| (axis(p) * 50.0) as usize).collect();
+        strata.sort_unstable();
+        assert_eq!(strata, (0..50).collect::<Vec<_>>());
+    }
+    assert_ne!(points, latin_hypercube(50, &mut Lcg::new(7)));
+}
+
+#[test]
+fn portable_math_tracks_std_within_rounding() {
+    use sim_core::portable::{ln, sin_cos};
+
+    let mut rng = Lcg::new(77);
+    for _ in 0..10_000 {
+        let u = 1.0 - rng.next_f64();
+        assert!((ln(u) - u.ln()).abs() <= 4.0 * f64::EPSILON * u.ln().abs().max(1.0));
+        let angle = std::f64::consts::TAU * rng.next_f64();
+        let (sin, cos) = sin_cos(angle);
+        assert!(
+            (sin - angle.sin()).abs() < 4.0 * f64::EPSILON,
+            "sin {}",
+            angle
+        );
+        assert!(
+            (cos - angle.cos()).abs() < 4.0 * f64::EPSILON,
+            "cos {}",
+            angle
+        );
+    }
+    assert_eq!(ln(1.0), 0.0);
+    assert!((ln(f64::MIN_POSITIVE / 8.0) - (f64::MIN_POSITIVE / 8.0).ln()).abs() < 1e-12);
+}
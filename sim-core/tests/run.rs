@@ -0,0 +1,676 @@
+use sim_core::diff::diff;
+use sim_core::merge::merge;
+use sim_core::output::{parse_aggregate, to_json, to_json_pretty};
+use sim_core::{widen, Accumulator, Aggregate, Meta, Params, RunConfig, RunOutput};
+
+const BALANCED: Params = Params {
+    aggression: 0.5,
+    greed: 0.5,
+    safety: 0.5,
+    focus: 0.5,
+};
+
+#[test]
+fn antithetic_pairs_mirror_the_unlock_noise() {
+    let config = RunConfig {
+        antithetic: true,
+        ..RunConfig::new(BALANCED, 5, 6)
+    };
+    let (rows, aggregate) = config.run();
+    // Noise-free unlock rate is 0.49; mirrored noise cancels within a pair.
+    for pair in rows.chunks(2) {
+        assert!((pair[0].unlock_rate + pair[1].unlock_rate - 0.98).abs() < 1e-9);
+    }
+    assert_eq!(aggregate.antithetic.map(|v| v.pairs), Some(3));
+}
+
+#[test]
+fn antithetic_report_is_omitted_without_a_complete_pair() {
+    let antithetic = |episodes, min_stability| RunConfig {
+        antithetic: true,
+        min_stability,
+        ..RunConfig::new(BALANCED, 5, episodes)
+    };
+    assert_eq!(antithetic(1, None).run().1.antithetic, None);
+    assert_eq!(
+        antithetic(2, None).run().1.antithetic.map(|v| v.pairs),
+        Some(1)
+    );
+    // Mirrored stabilities straddle this floor, so no pair keeps both halves.
+    let (rows, aggregate) = antithetic(40, Some(0.46)).run();
+    assert!(!rows.is_empty());
+    assert_eq!(aggregate.antithetic, None);
+}
+
+#[test]
+fn raised_stop_flag_truncates_the_run() {
+    use std::sync::atomic::AtomicBool;
+
+    static STOP: AtomicBool = AtomicBool::new(true);
+    let config = RunConfig {
+        stop: Some(&STOP),
+        ..RunConfig::new(BALANCED, 3, 1000)
+    };
+    let aggregate = config.stream(|_| panic!("no episode should be kept"));
+    assert_eq!(aggregate.episodes, 0);
+    assert!(aggregate.truncated);
+    assert!(!RunConfig::new(BALANCED, 3, 10).run().1.truncated);
+}
+
+#[test]
+fn seed_sweep_reruns_consecutive_seeds() {
+    use sim_core::seeds::run_seeds;
+
+    let base = RunConfig::new(BALANCED, 40, 200);
+    let sweep = run_seeds(&base, 3);
+    let seeds: Vec<u64> = sweep.runs.iter().map(|r| r.seed).collect();
+    assert_eq!(seeds, [40, 41, 42]);
+    assert_eq!(sweep.runs[0].aggregate, base.run().1);
+    assert_eq!(
+        sweep.runs[2].aggregate,
+        RunConfig::new(BALANCED, 42, 200).run().1
+    );
+    let rates = &sweep.seed_variance.objective_rates;
+    let mean = rates.iter().sum::<f64>() / 3.0;
+    assert!((sweep.seed_variance.objective_rate_mean - mean).abs() < 1e-12);
+}
+
+#[test]
+fn seeds_file_lists_irregular_seeds_in_order() {
+    use sim_core::seeds::{parse, run_seed_list};
+
+    let seeds = parse("# curated\n42\n\n  7 # lucky\n40\n").unwrap();
+    assert_eq!(seeds, [42, 7, 40]);
+    assert!(parse("# nothing\n\n").is_err());
+    assert!(parse("12\n-3\n").unwrap_err().contains("line 2"));
+
+    let base = RunConfig::new(BALANCED, 0, 200);
+    let sweep = run_seed_list(&base, &seeds);
+    assert_eq!(sweep.runs[1].seed, 7);
+    assert_eq!(
+        sweep.runs[2].aggregate,
+        RunConfig::new(BALANCED, 40, 200).run().1
+    );
+}
+
+#[test]
+fn threaded_seed_sweep_matches_the_sequential_one() {
+    use sim_core::seeds::run_seeds;
+
+    let base = RunConfig::new(BALANCED, 90, 150);
+    let sequential = run_seeds(&base, 7);
+    for threads in [2, 3, 16] {
+        let threaded = run_seeds(&RunConfig { threads, ..base }, 7);
+        assert_eq!(threaded, sequential, "{} threads", threads);
+    }
+}
+
+#[test]
+fn outliers_are_tagged_against_the_run_mean() {
+    use sim_core::AggregateOptions;
+
+    let config = RunConfig {
+        options: AggregateOptions {
+            outlier_k: 1.0,
+            ..AggregateOptions::default()
+        },
+        ..RunConfig::new(BALANCED, 8, 500)
+    };
+    let (rows, aggregate) = config.run();
+    let flagged = rows.iter().filter(|ep| ep.outlier).count();
+    assert!(flagged > 0);
+    assert_eq!(aggregate.outliers, Some(flagged));
+    for ep in &rows {
+        let far = (widen(ep.elapsed_s) - aggregate.mean_elapsed_s).abs() > aggregate.elapsed_std;
+        assert_eq!(ep.outlier, far);
+    }
+    assert_eq!(config.stream(|ep| assert!(!ep.outlier)).outliers, None);
+}
+
+#[test]
+fn shared_seed_gradient_recovers_unlock_weights() {
+    use sim_core::optimize::{gradient, Target};
+
+    // Both sides of each difference see the same noise, so the linear
+    // unlock model's weights come back exactly, up to `f32` rounding of the
+    // rows (over a difference of 0.04) in an `f32` build.
+    let tol = if cfg!(feature = "f32") { 1e-5 } else { 1e-9 };
+    let g = gradient(
+        &RunConfig::new(BALANCED, 9, 50),
+        Target::UnlockRate.into(),
+        0.02,
+    );
+    assert!((g.partials.aggression - 0.42).abs() < tol);
+    assert!((g.partials.greed - 0.36).abs() < tol);
+    assert!(g.partials.safety.abs() < tol);
+    assert!((g.partials.focus - 0.20).abs() < tol);
+}
+
+#[test]
+fn nelder_mead_climbs_the_linear_unlock_model() {
+    use sim_core::optimize::{nelder_mead, Target};
+
+    let result = nelder_mead(
+        &RunConfig::new(BALANCED, 9, 50),
+        Target::UnlockRate.into(),
+        0.1,
+        200,
+    );
+    assert!(result.trajectory.windows(2).all(|w| w[1] >= w[0]));
+    assert!(result.best.score > result.trajectory[0] + 0.2);
+    let p = result.centroid.params;
+    for x in [p.aggression, p.greed, p.safety, p.focus] {
+        assert!((0.0..=1.0).contains(&x), "{:?}", p);
+    }
+    // Unlock grows with aggression, greed and focus, so they run to the bound.
+    assert!(
+        p.aggression > 0.9 && p.greed > 0.9 && p.focus > 0.9,
+        "{:?}",
+        p
+    );
+    assert!(result.evaluations > result.iterations);
+}
+
+#[test]
+fn anneal_climbs_reproducibly_and_cools_on_its_schedule() {
+    use sim_core::optimize::{anneal, Schedule, Target, ANNEAL_FLOOR};
+
+    let base = RunConfig::new(BALANCED, 9, 50);
+    let run = |schedule| anneal(&base, Target::UnlockRate.into(), 0.1, 80, schedule, 0.05);
+    let result = run(Schedule::Exponential);
+    assert_eq!(result.trajectory.len(), 81);
+    assert!(result.trajectory.windows(2).all(|w| w[1] >= w[0]));
+    assert!(result.best.score > result.trajectory[0] + 0.1);
+    assert!(result.accepted > 0 && result.accepted <= result.iterations);
+    assert_eq!(result.evaluations, 81);
+    assert_eq!(run(Schedule::Exponential), result);
+    assert_ne!(run(Schedule::Linear).trajectory, result.trajectory);
+    let mut pcg = base;
+    pcg.rng = sim_core::RngKind::Pcg;
+    let drawn = anneal(
+        &pcg,
+        Target::UnlockRate.into(),
+        0.1,
+        80,
+        Schedule::Exponential,
+        0.05,
+    );
+    assert_ne!(drawn.trajectory, result.trajectory);
+
+    let t = |schedule: Schedule, k| schedule.temperature(2.0, k, 10);
+    assert_eq!(t(Schedule::Linear, 0), 2.0);
+    assert_eq!(t(Schedule::Linear, 10), 0.0);
+    assert!((t(Schedule::Exponential, 10) - 2.0 * ANNEAL_FLOOR).abs() < 1e-12);
+    assert!((t(Schedule::Logarithmic, 0) - 2.0).abs() < 1e-12);
+    assert!(t(Schedule::Logarithmic, 10) > t(Schedule::Exponential, 10));
+}
+
+#[test]
+fn pareto_front_keeps_only_non_dominated_points() {
+    use sim_core::pareto::{frontier, pareto, ParetoPoint};
+    use sim_core::sample::Sampler;
+
+    let point = |unlock_rate, stability_rate| ParetoPoint {
+        params: BALANCED,
+        unlock_rate,
+        stability_rate,
+    };
+    let points = [
+        point(0.9, 0.1),
+        point(0.5, 0.5),
+        point(0.4, 0.4),
+        point(0.1, 0.9),
+        point(0.5, 0.3),
+        point(0.5, 0.5),
+    ];
+    let rates: Vec<(f64, f64)> = frontier(&points)
+        .iter()
+        .map(|p| (p.unlock_rate, p.stability_rate))
+        .collect();
+    assert_eq!(rates, [(0.1, 0.9), (0.5, 0.5), (0.5, 0.5), (0.9, 0.1)]);
+
+    let front = pareto(&RunConfig::new(BALANCED, 2, 100), 40, Sampler::Lhs);
+    assert!(front.len() > 1);
+    for (i, p) in front.iter().enumerate() {
+        assert!(front.iter().all(|q| !q.dominates(p)), "{}", i);
+    }
+}
+
+#[test]
+fn saved_outputs_parse_back_to_one_aggregate_and_diff() {
+    let config = RunConfig::new(BALANCED, 4, 30);
+    let (rows, aggregate) = config.run();
+    let full = RunOutput {
+        meta: Meta::new(&config),
+        episodes: rows,
+        aggregate: aggregate.clone(),
+    };
+    let summary = parse_aggregate(&to_json(&aggregate).unwrap()).unwrap();
+    let ndjson = format!("{{\"meta\":{{}}}}\n{}\n", to_json(&aggregate).unwrap());
+    assert_eq!(
+        parse_aggregate(&to_json(&full).unwrap()),
+        Ok(summary.clone())
+    );
+    assert_eq!(
+        parse_aggregate(&to_json_pretty(&full).unwrap()),
+        Ok(summary.clone())
+    );
+    assert_eq!(parse_aggregate(&ndjson), Ok(summary.clone()));
+    assert!(parse_aggregate("{\"episodes\": 3}").is_err());
+
+    assert!(diff(&summary, &summary, 0.0)
+        .iter()
+        .all(|metric| !metric.flagged && metric.delta.is_none_or(|delta| delta == 0.0)));
+    let mut moved = summary.clone();
+    moved.objective_rate += 0.25;
+    moved.elapsed_p50 = None;
+    let metrics = diff(&summary, &moved, 0.1);
+    let flagged: Vec<_> = metrics.iter().filter(|m| m.flagged).collect();
+    assert_eq!(flagged.len(), 1);
+    assert_eq!(flagged[0].metric, "objective_rate");
+    assert!((flagged[0].delta.unwrap() - 0.25).abs() < 1e-12);
+    let p50 = metrics.iter().find(|m| m.metric == "elapsed_p50").unwrap();
+    assert_eq!((p50.new, p50.delta), (None, None));
+}
+
+#[test]
+fn merged_parts_match_one_accumulator_over_every_episode() {
+    let a = RunConfig::new(BALANCED, 1, 400).run();
+    let b = RunConfig::new(
+        Params {
+            greed: 0.9,
+            ..BALANCED
+        },
+        2,
+        250,
+    )
+    .run();
+    let mut whole = Accumulator::new();
+    a.0.iter().chain(&b.0).for_each(|ep| whole.push(ep));
+    let expected = whole.finish();
+
+    let saved = [&a.1, &b.1].map(|part| parse_aggregate(&to_json(part).unwrap()).unwrap());
+    for merged in [
+        merge(&[a.1.clone(), b.1.clone()], 0.95).unwrap(),
+        merge(&saved, 0.95).unwrap(),
+    ] {
+        assert_eq!(merged.episodes, 650);
+        assert_eq!(merged.objective_rate, expected.objective_rate);
+        assert_eq!(
+            merged.objective_rate_wilson_lo,
+            expected.objective_rate_wilson_lo
+        );
+        let close = |got: f64, want: f64| (got - want).abs() <= 1e-5 * want.abs().max(1.0);
+        for (got, want) in [
+            (merged.mean_elapsed_s, expected.mean_elapsed_s),
+            (merged.elapsed_var, expected.elapsed_var),
+            (merged.unlock_var, expected.unlock_var),
+            (merged.stability_std, expected.stability_std),
+            (
+                merged.elapsed_skewness.unwrap(),
+                expected.elapsed_skewness.unwrap(),
+            ),
+            (
+                merged.elapsed_kurtosis.unwrap(),
+                expected.elapsed_kurtosis.unwrap(),
+            ),
+            (
+                merged.unlock_stability_corr.unwrap(),
+                expected.unlock_stability_corr.unwrap(),
+            ),
+            (merged.elapsed_max.unwrap(), expected.elapsed_max.unwrap()),
+        ] {
+            assert!(close(got, want), "{} vs {}", got, want);
+        }
+        assert_eq!(merged.elapsed_p50, None);
+    }
+}
+
+#[test]
+fn merge_takes_the_hit_count_not_the_rounded_rate() {
+    let (_, part) = RunConfig::new(BALANCED, 3, 700).run();
+    // A rate no 6-decimal rounding of `objective_rate` could give back.
+    let big = Aggregate {
+        episodes: 3_000_001,
+        objective_rate: 0.5,
+        objective_hits: Some(1_694_513),
+        ..part.clone()
+    };
+    let merged = merge(&[big.clone(), part.clone()], 0.95).unwrap();
+    assert_eq!(
+        merged.objective_hits,
+        Some(1_694_513 + part.objective_hits.unwrap())
+    );
+
+    let old = Aggregate {
+        objective_hits: None,
+        ..part
+    };
+    let err = merge(&[big, old], 0.95).unwrap_err();
+    assert!(err.contains("part 1"), "{}", err);
+}
+
+#[test]
+fn sweep_grid_rejects_unbounded_ranges_before_allocating() {
+    use sim_core::sweep::{Grid, ParamRange};
+
+    for text in ["0:inf:0.1", "nan", "0:1:0", "0:1:inf"] {
+        assert!(ParamRange::parse(text).is_err(), "{}", text);
+    }
+    let tiny = ParamRange::parse("0:1:1e-300").unwrap();
+    assert!(tiny.count().is_err());
+    let wide = ParamRange::parse("0:1:1e-5").unwrap();
+    let fixed = ParamRange::fixed(0.5);
+    assert!(Grid::new([wide; 4]).is_err());
+    assert_eq!(
+        Grid::new([wide, wide, fixed, fixed]).unwrap().cells(),
+        100_001 * 100_001
+    );
+
+    let grid = Grid::new([ParamRange::parse("0:1:0.5").unwrap(), fixed, fixed, fixed]).unwrap();
+    let aggression: Vec<f64> = grid.iter().map(|p| p.aggression).collect();
+    assert_eq!(aggression, [0.0, 0.5, 1.0]);
+}
+
+#[test]
+fn run_id_hashes_only_the_inputs_that_differ_from_the_defaults() {
+    use sim_core::rng::seed_from_str;
+
+    let id = |text: &str| {
+        let input = format!("{} {}", env!("CARGO_PKG_VERSION"), text);
+        format!("{:016x}", seed_from_str(&input))
+    };
+    let defaults = RunConfig::new(BALANCED, 1, 10);
+    assert_eq!(defaults.run_id(), id("{}"));
+    let config = RunConfig {
+        warmup: 3,
+        ..RunConfig::new(
+            Params {
+                greed: 0.25,
+                ..BALANCED
+            },
+            7,
+            10,
+        )
+    };
+    assert_eq!(
+        config.run_id(),
+        id(r#"{"params":{"greed":0.25},"seed":7,"warmup":3}"#)
+    );
+    let resumed = RunConfig {
+        rng_state: Some(99),
+        ..defaults
+    };
+    assert_eq!(resumed.run_id(), id(r#"{"rng_state":99}"#));
+}
+
+#[test]
+fn replay_reruns_one_kept_episode() {
+    let shared = RunConfig {
+        warmup: 3,
+        ..RunConfig::new(BALANCED, 12, 40)
+    };
+    let independent = RunConfig {
+        independent_seeds: true,
+        antithetic: true,
+        ..shared
+    };
+    let threaded = RunConfig {
+        threads: 3,
+        ..shared
+    };
+    for config in [shared, independent, threaded] {
+        let (mut rows, _) = config.run();
+        for index in [0, 1, 17, 39] {
+            rows[index].outlier = false;
+            assert_eq!(config.replay(index), Some(rows[index]), "{:?}", config);
+        }
+    }
+    assert_eq!(threaded.replay(40), None);
+}
+
+#[test]
+fn min_stability_filters_rows_without_moving_the_stream() {
+    let plain = RunConfig::new(BALANCED, 14, 300);
+    let floor = 0.42;
+    let filtered = RunConfig {
+        min_stability: Some(floor),
+        ..plain
+    };
+    let (all, _) = plain.run();
+    let (kept, aggregate) = filtered.run();
+    let expected: Vec<(f64, f64)> = all
+        .iter()
+        .filter(|ep| widen(ep.stability) >= floor)
+        .map(|ep| (widen(ep.unlock_rate), widen(ep.elapsed_s)))
+        .collect();
+    let got: Vec<(f64, f64)> = kept
+        .iter()
+        .map(|ep| (widen(ep.unlock_rate), widen(ep.elapsed_s)))
+        .collect();
+    assert_eq!(got, expected);
+    assert!(!kept.is_empty() && kept.len() < all.len());
+    assert_eq!(aggregate.episodes, kept.len());
+    assert_eq!(aggregate.filtered_out, Some(all.len() - kept.len()));
+    assert!(aggregate.stability_min.unwrap() >= floor);
+    assert_eq!(plain.run().1.filtered_out, None);
+}
+
+#[test]
+fn antithetic_pairs_skip_halves_min_stability_dropped() {
+    let plain = RunConfig {
+        antithetic: true,
+        ..RunConfig::new(BALANCED, 21, 400)
+    };
+    let floor = 0.41;
+    let (all, _) = plain.run();
+    // Pair means of the pairs whose two halves both clear the floor.
+    let means: Vec<f64> = all
+        .chunks(2)
+        .filter(|pair| pair.iter().all(|ep| widen(ep.stability) >= floor))
+        .map(|pair| (widen(pair[0].elapsed_s) + widen(pair[1].elapsed_s)) / 2.0)
+        .collect();
+    let filtered = RunConfig {
+        min_stability: Some(floor),
+        ..plain
+    };
+    let report = filtered.run().1.antithetic.unwrap();
+    assert!(report.pairs > 0 && report.pairs < all.len() / 2);
+    assert_eq!(report.pairs, means.len());
+    let n = means.len() as f64;
+    let mean = means.iter().sum::<f64>() / n;
+    let var = means.iter().map(|m| (m - mean).powi(2)).sum::<f64>() / (n - 1.0) / n;
+    assert!((report.mean_elapsed_var - var).abs() < 1e-9 * var);
+}
+
+#[test]
+fn spent_time_budget_stops_at_the_next_clock_read() {
+    use std::time::Duration;
+
+    let budgeted = RunConfig {
+        time_budget: Some(Duration::ZERO),
+        ..RunConfig::new(BALANCED, 8, 10_000)
+    };
+    let aggregate = budgeted.stream(|_| {});
+    assert_eq!(aggregate.episodes, 1024);
+    assert!(!aggregate.truncated);
+    assert_eq!(aggregate, RunConfig::new(BALANCED, 8, 1024).stream(|_| {}));
+}
+
+#[test]
+fn until_successes_stops_at_the_nth_success() {
+    let config = RunConfig {
+        until_successes: Some(25),
+        ..RunConfig::new(BALANCED, 6, 100_000)
+    };
+    let (rows, aggregate) = config.run();
+    assert_eq!(rows.iter().filter(|ep| ep.objective_complete).count(), 25);
+    assert!(rows.last().unwrap().objective_complete);
+    let inverse = aggregate.inverse_sampling.unwrap();
+    assert!(inverse.reached);
+    assert_eq!(inverse.episodes, rows.len());
+    assert!((inverse.rate - 24.0 / (rows.len() - 1) as f64).abs() < 1e-12);
+
+    let capped = RunConfig {
+        episodes: 10,
+        ..config
+    };
+    let inverse = capped.stream(|_| {}).inverse_sampling.unwrap();
+    assert!(!inverse.reached);
+    assert_eq!(inverse.episodes, 10);
+    assert!((inverse.rate - inverse.successes as f64 / 10.0).abs() < 1e-12);
+}
+
+#[test]
+fn score_reduces_a_run_the_way_the_optimizers_do() {
+    use sim_core::optimize::{evaluate, Target, Weights};
+    use sim_core::{score, simulate, Objective};
+
+    let aggregate = simulate(BALANCED, 12, 300).aggregate;
+    assert_eq!(
+        score(BALANCED, 12, 300, Objective::StabilityRate),
+        aggregate.stability_rate
+    );
+    let weights = Weights {
+        objective_rate: 1.0,
+        stability_rate: 0.5,
+        unlock_rate: -0.25,
+        ..Weights::default()
+    };
+    let weighted = score(BALANCED, 12, 300, Objective::Weighted(weights));
+    let expected =
+        aggregate.objective_rate + 0.5 * aggregate.stability_rate - 0.25 * aggregate.unlock_rate;
+    assert!((weighted - expected).abs() < 1e-12);
+
+    let base = RunConfig::new(BALANCED, 12, 300);
+    let trial = evaluate(&base, BALANCED, Target::ObjectiveRate.into());
+    assert_eq!(
+        trial.score,
+        score(BALANCED, 12, 300, Objective::ObjectiveRate)
+    );
+}
+
+#[test]
+fn objective_weights_parse_and_penalize_normalized_elapsed() {
+    use sim_core::optimize::{normalized_elapsed, random_search, Weights};
+    use sim_core::sample::Sampler;
+    use sim_core::{simulate, Objective, ELAPSED_MAX_S, ELAPSED_MIN_S};
+
+    let weights = Weights::parse("objective_rate=1, mean_elapsed=-0.5").unwrap();
+    assert_eq!((weights.objective_rate, weights.unlock_rate), (1.0, 0.0));
+    assert_eq!(weights.mean_elapsed, -0.5);
+    assert!(Weights::parse("speed=1").is_err());
+    assert!(Weights::parse("objective_rate").is_err());
+    assert_eq!(normalized_elapsed(ELAPSED_MIN_S), 0.0);
+    assert_eq!(normalized_elapsed(ELAPSED_MAX_S), 1.0);
+
+    let aggregate = simulate(BALANCED, 3, 200).aggregate;
+    let expected = aggregate.objective_rate - 0.5 * normalized_elapsed(aggregate.mean_elapsed_s);
+    assert_eq!(Objective::Weighted(weights).score(&aggregate), expected);
+
+    let base = RunConfig::new(BALANCED, 3, 200);
+    let result = random_search(&base, Objective::Weighted(weights), 10, Sampler::Halton);
+    assert_eq!(
+        result.best.score,
+        Objective::Weighted(weights).score(&result.best.aggregate)
+    );
+}
+
+#[test]
+fn sobol_indices_are_reproducible_and_zero_without_variance() {
+    use sim_core::optimize::Target;
+    use sim_core::sobol::sobol;
+    use sim_core::ParamBounds;
+
+    let base = RunConfig::new(BALANCED, 21, 60);
+    let result = sobol(&base, Target::ObjectiveRate.into(), 12);
+    assert_eq!(result.samples, 12);
+    assert_eq!(result.evaluations, 72);
+    assert!(result.variance > 0.0);
+    let indices = [
+        result.first_order.aggression,
+        result.first_order.greed,
+        result.first_order.safety,
+        result.first_order.focus,
+    ];
+    assert!(indices.iter().all(|s| s.is_finite()));
+    assert_eq!(sobol(&base, Target::ObjectiveRate.into(), 12), result);
+
+    let pinned = RunConfig {
+        bounds: ParamBounds { min: 0.5, max: 0.5 },
+        ..base
+    };
+    let flat = sobol(&pinned, Target::StabilityRate.into(), 4);
+    assert_eq!(flat.variance, 0.0);
+    assert_eq!(flat.first_order, BALANCED.map(|_| 0.0));
+}
+
+#[test]
+fn sim_config_builder_runs_like_simulate_and_round_trips_as_json() {
+    use sim_core::{simulate, Noise, ParamBounds, SimConfig};
+
+    let config = SimConfig::new()
+        .episodes(300)
+        .seed(7)
+        .params(BALANCED)
+        .build()
+        .unwrap();
+    assert_eq!(config.run(), simulate(BALANCED, 7, 300));
+    assert_eq!(SimConfig::new().build().unwrap(), SimConfig::default());
+
+    let gaussian = SimConfig::new()
+        .episodes(50)
+        .noise(Noise::Gaussian { sigma: 0.5 })
+        .threads(2)
+        .build()
+        .unwrap();
+    let text = serde_json::to_string(&gaussian).unwrap();
+    let back: SimConfig = serde_json::from_str(&text).unwrap();
+    assert_eq!(back, gaussian);
+    assert_eq!(back.run(), gaussian.run());
+    let partial: SimConfig = serde_json::from_str(r#"{"seed": 7, "episodes": 300}"#).unwrap();
+    assert_eq!(partial, config);
+
+    assert!(SimConfig::new().episodes(0).build().is_err());
+    let narrow = ParamBounds { min: 0.6, max: 0.9 };
+    let err = SimConfig::new().bounds(narrow).build().unwrap_err();
+    assert!(err.contains("aggression"), "{}", err);
+}
+
+#[test]
+fn timed_episodes_carry_compute_ns_and_nothing_else_changes() {
+    use sim_core::output::csv_row;
+
+    let plain = RunConfig::new(BALANCED, 17, 200);
+    let (rows, aggregate) = plain.run();
+    assert!(rows.iter().all(|ep| ep.compute_ns.is_none()));
+    for threads in [1, 3] {
+        let timed = RunConfig {
+            time_episodes: true,
+            threads,
+            ..plain
+        };
+        let (timed_rows, timed_aggregate) = timed.run();
+        assert!(timed_rows.iter().all(|ep| ep.compute_ns.is_some()));
+        if threads == 1 {
+            assert_eq!(timed_aggregate, aggregate);
+            let stripped: Vec<_> = timed_rows
+                .iter()
+                .map(|ep| sim_core::Episode {
+                    compute_ns: None,
+                    ..*ep
+                })
+                .collect();
+            assert_eq!(stripped, rows);
+        }
+    }
+    let timed = RunConfig {
+        time_episodes: true,
+        ..plain
+    }
+    .run()
+    .0;
+    let ns = timed[0].compute_ns.unwrap();
+    assert!(csv_row(&timed[0]).ends_with(&format!(",{}", ns)));
+    assert_eq!(csv_row(&rows[0]).split(',').count(), 6);
+}
@@ -0,0 +1,63 @@
+// The snapshots pin `f64` episodes; an `f32` build rounds every row first.
+#![cfg(not(feature = "f32"))]
+
+use std::process::Command;
+
+use serde_json::Value;
+
+/// Pins the default-parameter episodes for `--episodes 5 --seed 42`, as
+/// rounded to 6 decimals in the CLI output. Regenerate the snapshot only
+/// when a model change is meant to move the numbers.
+#[test]
+fn default_params_seed_42_matches_snapshot() {
+    let output = Command::new(env!("CARGO_BIN_EXE_sim-core"))
+        .args(["--episodes", "5", "--seed", "42"])
+        .output()
+        .expect("run sim-core");
+    assert!(output.status.success());
+
+    let run: Value = serde_json::from_slice(&output.stdout).expect("stdout is JSON");
+    let snapshot: Value = serde_json::from_str(include_str!("snapshots/episodes5_seed42.json"))
+        .expect("snapshot is JSON");
+    assert_eq!(run["episodes"], snapshot);
+}
+
+/// Gaussian noise goes through `ln`/`cos`; with `--deterministic` those are
+/// the portable versions, so this snapshot must match on every target CI
+/// runs (x86_64 and aarch64). Written at `--precision 17`, which keeps every
+/// bit, and compared by `to_bits` rather than to 6 decimals.
+#[test]
+fn deterministic_gaussian_seed_42_matches_snapshot() {
+    let output = Command::new(env!("CARGO_BIN_EXE_sim-core"))
+        .args([
+            "--episodes",
+            "5",
+            "--seed",
+            "42",
+            "--noise",
+            "gaussian",
+            "--deterministic",
+            "--precision",
+            "17",
+        ])
+        .output()
+        .expect("run sim-core");
+    assert!(output.status.success());
+
+    let run: Value = serde_json::from_slice(&output.stdout).expect("stdout is JSON");
+    let snapshot: Value =
+        serde_json::from_str(include_str!("snapshots/episodes5_seed42_gaussian.json"))
+            .expect("snapshot is JSON");
+    let (got, want) = (
+        run["episodes"].as_array().unwrap(),
+        snapshot.as_array().unwrap(),
+    );
+    assert_eq!(got.len(), want.len());
+    for (ep, expected) in got.iter().zip(want) {
+        for field in ["unlock_rate", "stability", "elapsed_s"] {
+            let bits = |ep: &Value| ep[field].as_f64().expect("float field").to_bits();
+            assert_eq!(bits(ep), bits(expected), "{} of {}", field, ep);
+        }
+    }
+    assert_eq!(run["episodes"], snapshot);
+}
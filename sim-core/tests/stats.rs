@@ -0,0 +1,381 @@
+use sim_core::stats::{
+    iqr, median, nth_element, percentile, select_percentile, select_trimmed_mean, sorted_copy,
+    trimmed_mean,
+};
+use sim_core::{widen, Episode, Lcg, Params, RunConfig};
+
+#[test]
+fn median_of_odd_count_is_middle_value() {
+    assert_eq!(median(&[1.0, 2.0, 7.0]), 2.0);
+}
+
+#[test]
+fn median_of_even_count_averages_middle_pair() {
+    assert_eq!(median(&[1.0, 2.0, 4.0, 9.0]), 3.0);
+}
+
+#[test]
+fn iqr_interpolates_quartiles() {
+    // Quartile ranks 0.75 and 2.25 over [1, 2, 4, 9].
+    assert_eq!(
+        iqr(&[1.0, 2.0, 4.0, 9.0]),
+        (4.0 + 0.25 * 5.0) - (1.0 + 0.75 * 1.0)
+    );
+    assert_eq!(iqr(&[1.0, 2.0, 3.0, 4.0, 5.0]), 2.0);
+}
+
+#[test]
+fn single_episode_median_is_the_episode() {
+    let params = Params {
+        aggression: 0.5,
+        greed: 0.5,
+        safety: 0.5,
+        focus: 0.5,
+    };
+    let (rows, aggregate) = RunConfig::new(params, 7, 1).run();
+    let ep: Episode = rows[0];
+    assert_eq!(aggregate.unlock_median, Some(widen(ep.unlock_rate)));
+    assert_eq!(aggregate.stability_median, Some(widen(ep.stability)));
+    assert_eq!(aggregate.elapsed_median, Some(widen(ep.elapsed_s)));
+    assert_eq!(aggregate.elapsed_iqr, Some(0.0));
+}
+
+#[test]
+fn zero_episodes_report_null_extremes() {
+    let aggregate = sim_core::Accumulator::new().finish();
+    assert_eq!(aggregate.elapsed_min, None);
+    let json = serde_json::to_value(&aggregate).unwrap();
+    assert!(json["unlock_max"].is_null());
+}
+
+#[test]
+fn streamed_aggregate_matches_full_run() {
+    let params = Params {
+        aggression: 0.8,
+        greed: 0.3,
+        safety: 0.6,
+        focus: 0.4,
+    };
+    let config = RunConfig::new(params, 99, 500);
+    let (_, full) = config.run();
+    let streamed = config.stream(|_| {});
+    let full = serde_json::to_value(&full).unwrap();
+    let streamed = serde_json::to_value(&streamed).unwrap();
+    for (key, value) in streamed.as_object().unwrap() {
+        // Streamed percentiles are P² estimates; the full run sorts rows.
+        if !key.starts_with("elapsed_p") {
+            assert_eq!(&full[key], value, "{}", key);
+        }
+    }
+}
+
+#[test]
+fn p2_tracks_exact_quantiles() {
+    use sim_core::stats::{percentile, sorted_copy, P2Quantile};
+    use sim_core::Lcg;
+
+    // Exponential-ish skewed sample from a fixed stream.
+    let mut rng = Lcg::new(2024);
+    let values: Vec<f64> = (0..20_000).map(|_| -(1.0 - rng.next_f64()).ln()).collect();
+    let sorted = sorted_copy(values.iter().copied());
+    for p in [0.5, 0.9, 0.99] {
+        let mut q = P2Quantile::new(p);
+        for &v in &values {
+            q.push(v);
+        }
+        let exact = percentile(&sorted, p);
+        let estimate = q.estimate().unwrap();
+        assert!(
+            (estimate - exact).abs() / exact < 0.02,
+            "p{}: estimate {} vs exact {}",
+            p,
+            estimate,
+            exact
+        );
+    }
+    let mut small = P2Quantile::new(0.5);
+    for v in [3.0, 1.0, 2.0] {
+        small.push(v);
+    }
+    assert_eq!(small.estimate(), Some(2.0));
+}
+
+#[test]
+fn correlation_of_linear_pairs_is_one() {
+    let mut corr = sim_core::stats::RunningCorr::default();
+    for i in 0..10 {
+        let x = i as f64;
+        corr.push(x, 3.0 - 2.0 * x);
+    }
+    assert!((corr.correlation().unwrap() + 1.0).abs() < 1e-12);
+
+    let mut single = sim_core::stats::RunningCorr::default();
+    single.push(0.4, 0.6);
+    assert_eq!(single.correlation(), None);
+}
+
+#[test]
+fn p_values_match_tables() {
+    use sim_core::stats::{normal_cdf, student_t_p_value, Z_95};
+
+    assert!((normal_cdf(Z_95) - 0.975).abs() < 1e-6);
+    assert!((normal_cdf(-1.0) - 0.158_655).abs() < 1e-6);
+    // Two-sided 5% critical values of Student's t.
+    assert!((student_t_p_value(2.228_139, 10.0) - 0.05).abs() < 1e-5);
+    assert!((student_t_p_value(12.706_205, 1.0) - 0.05).abs() < 1e-5);
+    assert!((student_t_p_value(0.0, 7.5) - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn z_values_match_coverage_levels() {
+    use sim_core::stats::{normal_quantile, two_sided_z, Z_95};
+
+    assert!((two_sided_z(0.95) - Z_95).abs() < 1e-8);
+    assert!((two_sided_z(0.99) - 2.575_829_3).abs() < 1e-6);
+    assert!((normal_quantile(0.001) + 3.090_232_3).abs() < 1e-6);
+    assert_eq!(normal_quantile(0.5), 0.0);
+}
+
+#[test]
+fn wilson_interval_stays_inside_unit_range_at_extremes() {
+    use sim_core::stats::{wilson_interval, Z_95};
+
+    let (lo, hi) = wilson_interval(0, 50, Z_95);
+    assert_eq!(lo, 0.0);
+    assert!(hi > 0.0 && hi < 0.1, "{}", hi);
+    let (lo, hi) = wilson_interval(50, 50, Z_95);
+    assert!(lo > 0.9 && lo < 1.0, "{}", lo);
+    assert_eq!(hi, 1.0);
+}
+
+#[test]
+fn running_moments_match_two_pass_formulas() {
+    use sim_core::stats::Running;
+
+    let values = [1.0, 2.0, 2.0, 3.0, 9.0, 4.5, 0.5];
+    let mut running = Running::default();
+    values.iter().for_each(|&v| running.push(v));
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let moment = |k: i32| values.iter().map(|v| (v - mean).powi(k)).sum::<f64>() / n;
+    let skew = moment(3) / moment(2).powf(1.5);
+    let kurt = moment(4) / moment(2).powi(2) - 3.0;
+    assert!((running.skewness().unwrap() - skew).abs() < 1e-12);
+    assert!((running.excess_kurtosis().unwrap() - kurt).abs() < 1e-12);
+
+    let mut flat = Running::default();
+    flat.push(4.0);
+    assert_eq!(flat.skewness(), None);
+    flat.push(4.0);
+    assert_eq!(flat.excess_kurtosis(), None);
+}
+
+#[test]
+fn cv_is_std_over_mean_and_null_near_zero_mean() {
+    use sim_core::stats::Running;
+
+    let mut running = Running::default();
+    [1.0, 2.0, 3.0].iter().for_each(|&v| running.push(v));
+    assert_eq!(running.cv(), Some(0.5));
+
+    let mut centred = Running::default();
+    [-1.0, 1.0].iter().for_each(|&v| centred.push(v));
+    assert_eq!(centred.cv(), None);
+    assert_eq!(Running::default().cv(), None);
+}
+
+#[test]
+fn trimmed_mean_drops_both_tails() {
+    use sim_core::stats::trimmed_mean;
+
+    let sorted = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 100.0];
+    assert_eq!(trimmed_mean(&sorted, 0.0), 14.5);
+    assert_eq!(trimmed_mean(&sorted, 0.1), 5.5);
+    assert_eq!(trimmed_mean(&sorted, 0.49), 5.5);
+    assert_eq!(trimmed_mean(&[7.0], 0.4), 7.0);
+}
+
+#[test]
+fn histogram_modes_keep_prominent_peaks_and_merge_plateaus() {
+    use sim_core::stats::{modes, Histogram};
+    use sim_core::Metric;
+
+    let histogram = Histogram {
+        metric: Metric::Elapsed,
+        edges: (0..=9).map(f64::from).collect(),
+        counts: vec![1, 5, 2, 3, 1, 0, 4, 4, 1],
+        modes: Vec::new(),
+    };
+    let found = modes(&histogram, 0.1);
+    let centers: Vec<f64> = found.iter().map(|mode| mode.center).collect();
+    assert_eq!(centers, [1.5, 3.5, 7.0]);
+    let prominences: Vec<f64> = found.iter().map(|mode| mode.prominence).collect();
+    assert_eq!(prominences, [1.0, 0.2, 0.8]);
+    assert_eq!(modes(&histogram, 0.25).len(), 2);
+
+    let params = Params {
+        aggression: 0.5,
+        greed: 0.5,
+        safety: 0.5,
+        focus: 0.5,
+    };
+    let config = RunConfig {
+        options: sim_core::AggregateOptions {
+            histogram: Some(Metric::Elapsed),
+            ..Default::default()
+        },
+        ..RunConfig::new(params, 3, 2000)
+    };
+    let histogram = config.run().1.histogram.unwrap();
+    assert!(!histogram.modes.is_empty());
+    assert!(histogram.modes.iter().any(|mode| mode.prominence == 1.0));
+}
+
+#[test]
+fn covariance_matches_two_pass_sums_and_is_zero_for_one_episode() {
+    use sim_core::stats::RunningCovariance;
+    use sim_core::{AggregateOptions, TimeUnit};
+
+    let samples = [
+        [1.0, 2.0, 10.0],
+        [2.0, 1.0, 30.0],
+        [4.0, 0.5, 20.0],
+        [3.0, 4.0, 60.0],
+    ];
+    let mut acc = RunningCovariance::<3>::default();
+    samples.iter().for_each(|&x| acc.push(x));
+    let mean: Vec<f64> = (0..3)
+        .map(|i| samples.iter().map(|x| x[i]).sum::<f64>() / 4.0)
+        .collect();
+    let covariance = acc.covariance();
+    let correlation = acc.correlation();
+    for i in 0..3 {
+        for j in 0..3 {
+            let sum: f64 = samples
+                .iter()
+                .map(|x| (x[i] - mean[i]) * (x[j] - mean[j]))
+                .sum();
+            assert!((covariance[i][j] - sum / 3.0).abs() < 1e-12);
+            assert_eq!(covariance[i][j], covariance[j][i]);
+        }
+        assert!((correlation[i][i] - 1.0).abs() < 1e-12);
+    }
+
+    let params = Params {
+        aggression: 0.5,
+        greed: 0.5,
+        safety: 0.5,
+        focus: 0.5,
+    };
+    let options = AggregateOptions {
+        covariance: true,
+        ..Default::default()
+    };
+    let aggregate = RunConfig {
+        options,
+        ..RunConfig::new(params, 5, 400)
+    }
+    .run()
+    .1;
+    let matrix = aggregate.covariance.clone().unwrap();
+    assert!((matrix.covariance[0][0] - aggregate.unlock_var).abs() < 1e-9);
+    assert!((matrix.covariance[2][2] - aggregate.elapsed_var).abs() < 1e-6);
+    let corr = aggregate.unlock_stability_corr.unwrap();
+    assert!((matrix.correlation[0][1] - corr).abs() < 1e-9);
+    let minutes = aggregate.in_unit(TimeUnit::Minutes).covariance.unwrap();
+    assert!((minutes.covariance[2][2] * 3600.0 - matrix.covariance[2][2]).abs() < 1e-6);
+    assert_eq!(minutes.correlation, matrix.correlation);
+
+    let single = RunConfig {
+        options,
+        ..RunConfig::new(params, 5, 1)
+    }
+    .run()
+    .1
+    .covariance
+    .unwrap();
+    assert_eq!(single.covariance, [[0.0; 3]; 3]);
+    assert_eq!(single.correlation, [[0.0; 3]; 3]);
+    assert!(RunConfig::new(params, 5, 10).run().1.covariance.is_none());
+}
+
+#[test]
+fn winsorize_clamps_every_metric_before_summarizing() {
+    use sim_core::{winsorized, Accumulator, AggregateOptions, Meta};
+
+    let params = Params {
+        aggression: 0.5,
+        greed: 0.5,
+        safety: 0.5,
+        focus: 0.5,
+    };
+    let plain = RunConfig::new(params, 4, 500);
+    let (raw, _) = plain.run();
+    let clamped = winsorized(&raw, 0.05);
+    assert_eq!(clamped.len(), raw.len());
+    assert_eq!(winsorized(&raw, 0.0), raw);
+    let max = |rows: &[Episode]| {
+        rows.iter()
+            .map(|ep| widen(ep.elapsed_s))
+            .fold(f64::MIN, f64::max)
+    };
+    assert!(max(&clamped) < max(&raw));
+
+    let config = RunConfig {
+        options: AggregateOptions {
+            winsorize: 0.05,
+            ..Default::default()
+        },
+        ..plain
+    };
+    let (rows, aggregate) = config.run();
+    assert_eq!(rows, raw, "the rows themselves are not clamped");
+    let mut acc = Accumulator::new();
+    clamped.iter().for_each(|ep| acc.push(ep));
+    let expected = acc.finish();
+    assert_eq!(aggregate.mean_elapsed_s, expected.mean_elapsed_s);
+    assert_eq!(aggregate.unlock_std, expected.unlock_std);
+    assert_eq!(
+        aggregate.unlock_stability_corr,
+        expected.unlock_stability_corr
+    );
+    assert_eq!(aggregate.elapsed_max, Some(max(&clamped)));
+    assert_eq!(config.stream(|_| {}).elapsed_var, aggregate.elapsed_var);
+    assert_eq!(Meta::new(&config).winsorize, Some(0.05));
+    assert_eq!(Meta::new(&plain).winsorize, None);
+}
+
+#[test]
+fn selection_matches_a_full_sort_on_random_data() {
+    let mut rng = Lcg::new(2024);
+    for n in [1, 2, 3, 10, 101, 1000] {
+        // Coarse values so long runs of duplicates go through the partition.
+        let raw: Vec<f64> = (0..n)
+            .map(|_| (rng.next_f64() * 20.0).floor() / 4.0)
+            .collect();
+        let sorted = sorted_copy(raw.iter().copied());
+        for (k, &expected) in sorted.iter().enumerate() {
+            let mut values = raw.clone();
+            assert_eq!(nth_element(&mut values, k), expected, "n {} k {}", n, k);
+            assert!(values[..k].iter().all(|&v| v <= expected));
+            assert!(values[k + 1..].iter().all(|&v| v >= expected));
+        }
+        for p in [0.0, 0.25, 0.5, 0.9, 0.99, 1.0] {
+            let mut values = raw.clone();
+            assert_eq!(select_percentile(&mut values, p), percentile(&sorted, p));
+        }
+        // Nothing cut: the plain mean in the given order, values untouched.
+        let mut values = raw.clone();
+        let mean = raw.iter().sum::<f64>() / n as f64;
+        assert_eq!(
+            select_trimmed_mean(&mut values, 0.0).to_bits(),
+            mean.to_bits()
+        );
+        assert_eq!(values, raw);
+        for trim in [0.1, 0.25] {
+            let mut values = raw.clone();
+            let got = select_trimmed_mean(&mut values, trim);
+            assert_eq!(got.to_bits(), trimmed_mean(&sorted, trim).to_bits());
+        }
+    }
+}